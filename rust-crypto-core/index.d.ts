@@ -9,10 +9,17 @@ declare module 'truefa-crypto-core' {
      * @param base32Secret - The Base32 encoded secret key
      */
     constructor(base32Secret: string);
+
+    /** Create from a hex encoded key */
+    static fromHex(hexSecret: string): SecureSecret;
+
+    /** Create from a base64 encoded key (padding optional) */
+    static fromBase64(base64Secret: string): SecureSecret;
     
     /**
      * Explicitly clear the secret from memory
      * Proactive memory cleanup for sensitive data
+     * Any later use of this secret throws "Secret has been cleared"
      */
     clear(): void;
   }
@@ -29,6 +36,557 @@ declare module 'truefa-crypto-core' {
     
     /** Error message if operation failed */
     error?: string;
+
+    /**
+     * Machine-readable error category if operation failed:
+     * INVALID_ENCODING, INVALID_FORMAT, TRUNCATED, AUTHENTICATION_FAILED, INVALID_UTF8, ENCRYPTION_FAILED
+     * PASSWORD_TOO_LONG (more than 4096 bytes), TIME_LOCKED (time-locked blob opened too early)
+     * or SUSPICIOUS_PLAINTEXT (strict decryption found control characters)
+     */
+    errorCode?: string;
+  }
+
+  /**
+   * In-memory 256-bit data encryption key
+   * Every record gets a random 96-bit nonce, so records can be encrypted in parallel;
+   * envelope records at derived nonces are sealed under a separate HKDF subkey
+   */
+  export class DerivedKey {
+    /** Generate a fresh random key */
+    static generate(): DerivedKey;
+
+    /**
+     * Load an existing key
+     * @param key - 32 key bytes
+     */
+    static fromBytes(key: Buffer): DerivedKey;
+  }
+
+  /**
+   * Forward-secret encryptor for append-only logs
+   * Each entry uses a fresh key and the previous chaining key is discarded
+   */
+  export class RatchetEncryptor {
+    /**
+     * Start a log from a seed
+     * @param seed - 32 random bytes, kept by the log reader only
+     */
+    constructor(seed: Buffer);
+
+    /** Index the next entry will be written with */
+    readonly index: number;
+
+    /**
+     * Encrypt one log entry and ratchet the key forward
+     * @param plaintext - Entry contents
+     * @returns Encrypted entry (index + iv + ciphertext)
+     * @throws Once the log holds 2^20 entries
+     */
+    nextEntry(plaintext: string): Buffer;
+  }
+
+  /**
+   * Receiving side of a device-to-device vault transfer
+   * Holds an ephemeral key and can only be completed once
+   */
+  export class TransferOffer {
+    /** Base64 encoded X25519 public key to show to the sending device */
+    readonly publicKey: string;
+
+    /**
+     * Open the sender's response
+     * @param response - Response produced by accept_transfer_offer on the sending device
+     * @returns The transferred vault (still master password encrypted) and the SAS code
+     */
+    completeTransfer(response: TransferResponse): TransferCompletion;
+  }
+
+  /**
+   * Message sent from the sending device to the receiving device
+   */
+  export interface TransferResponse {
+    /** Base64 encoded X25519 public key of the sender */
+    publicKey: string;
+
+    /** Base64 encoded sealed vault */
+    payload: string;
+  }
+
+  /**
+   * Result of accepting a transfer offer on the sending device
+   */
+  export interface TransferAcceptance {
+    /** Response to send to the receiving device */
+    response: TransferResponse;
+
+    /** Short authentication string, display locally only */
+    sas: string;
+  }
+
+  /**
+   * Result of completing a transfer on the receiving device
+   */
+  export interface TransferCompletion {
+    /** The transferred vault, encrypted with the original master password */
+    vault: string;
+
+    /** Short authentication string to compare with the sending device */
+    sas: string;
+  }
+
+  /**
+   * Authenticator account as stored in the vault
+   */
+  export interface Account {
+    /** Unique identifier for the account */
+    id: string;
+
+    /** User account name or email */
+    name: string;
+
+    /** Service provider name */
+    issuer: string;
+
+    /** Base32 encoded secret key */
+    secret: string;
+
+    /** Unix timestamp (milliseconds) of account creation */
+    createdAt: number;
+
+    /** HMAC algorithm, SHA1 when absent */
+    algorithm?: string;
+
+    /** Number of digits, 6 when absent */
+    digits?: number;
+
+    /** Time step in seconds, 30 when absent */
+    period?: number;
+
+    /** "totp" (default), "hotp" or "steam" */
+    otpType?: string;
+
+    /** Next HOTP counter value, required for HOTP accounts */
+    counter?: number;
+
+    /** Unix timestamp (milliseconds) of the last code request */
+    lastUsed?: number;
+
+    /** Number of code requests */
+    useCount?: number;
+
+    /** Folder the account is filed under */
+    category?: string;
+
+    /** Secret replaced by a rotation, kept during its grace period */
+    previousSecret?: string;
+
+    /** Unix time in milliseconds until which previousSecret is used */
+    previousSecretUntil?: number;
+  }
+
+  /**
+   * Current code of one account in a category group
+   */
+  export interface AccountCode {
+    id: string;
+    name: string;
+    issuer: string;
+
+    /** Absent when generation failed; see error */
+    code?: string;
+
+    error?: string;
+  }
+
+  /**
+   * Accounts of one category, sorted by issuer then name
+   */
+  export interface CategoryGroup {
+    /** Absent for uncategorized accounts */
+    category?: string;
+
+    accounts: AccountCode[];
+  }
+
+  /**
+   * Colored badge for an account in the list
+   */
+  export interface AvatarInfo {
+    /** "#rrggbb", derived from the issuer */
+    colorHex: string;
+
+    /** One or two uppercase letters, or "?" */
+    initials: string;
+  }
+
+  /**
+   * Key derivation details of an encrypted vault
+   */
+  export interface BlobInfo {
+    /** Key derivation function, e.g. "PBKDF2-SHA256" */
+    kdf: string;
+
+    /** KDF iteration count */
+    iterations: number;
+  }
+
+  export const enum Severity {
+    Info = 'Info',
+    Low = 'Low',
+    Medium = 'Medium',
+    High = 'High'
+  }
+
+  /**
+   * A single issue reported by the security dashboard
+   */
+  export interface SecurityFinding {
+    /** Stable identifier, e.g. "WEAK_SECRETS" */
+    id: string;
+
+    severity: Severity;
+
+    /** Number of affected accounts (0 for vault-level findings) */
+    count: number;
+
+    /** Human readable description */
+    message: string;
+
+    /** Suggested fix */
+    remediation: string;
+  }
+
+  /**
+   * Aggregate security posture of a vault
+   */
+  export interface SecurityReport {
+    totalAccounts: number;
+    sha1Accounts: number;
+    strongerAlgorithmAccounts: number;
+    weakSecretAccounts: number;
+    missingIssuerAccounts: number;
+    kdfUpToDate: boolean;
+    findings: SecurityFinding[];
+  }
+
+  /**
+   * Changes between two versions of a vault, keyed by account id
+   */
+  export interface VaultDiff {
+    added: Account[];
+
+    /** Ids of removed accounts */
+    removed: string[];
+
+    modified: Account[];
+
+    /** Account ids of the new version, in order */
+    order: string[];
+  }
+
+  /**
+   * Optional TOTP parameters (defaults: 6 digits, 30 seconds, SHA1, t0 = 0)
+   */
+  export interface TotpParams {
+    /** Number of digits in the code (6-8) */
+    digits?: number;
+
+    /** Time step in seconds */
+    period?: number;
+
+    /** HMAC algorithm: SHA1, SHA256 or SHA512 */
+    algorithm?: string;
+
+    /** Unix time the first time step starts */
+    t0?: number;
+  }
+
+  /**
+   * One code to check in a batch verification
+   */
+  export interface VerifyRequest {
+    /** Base32 secret */
+    secret: string;
+
+    code: string;
+
+    params?: TotpParams;
+
+    /** Steps accepted either side (default 1) */
+    window?: number;
+  }
+
+  /**
+   * Result of a verbose code verification
+   */
+  export interface VerifyResult {
+    /** Whether the code matched any window */
+    valid: boolean;
+
+    /** Window offset that matched (-1, 0, +1, ...), absent when invalid */
+    matchedOffset?: number;
+  }
+
+  /**
+   * Result of a verification that accepts each time step only once
+   */
+  export interface ReplayCheckResult {
+    valid: boolean;
+    matchedOffset?: number;
+
+    /** Counter of the accepted step; store as the next lastAcceptedCounter */
+    counter?: number;
+
+    /** The code matched a step that was already used */
+    replayed: boolean;
+  }
+
+  /**
+   * Verification result with the remaining validity of the matched code
+   */
+  export interface VerifyReport {
+    valid: boolean;
+
+    /** Window offset that matched, absent when invalid */
+    offset?: number;
+
+    /** Seconds until the code is no longer accepted, absent when invalid */
+    remainingSeconds?: number;
+  }
+
+  /**
+   * Result of confirming a new account with a user-entered code
+   */
+  export interface EnrollmentResult {
+    confirmed: boolean;
+
+    /** Window offset of the match; non-zero suggests clock skew */
+    offset?: number;
+  }
+
+  /**
+   * Best-guess classification of a pasted secret
+   */
+  export const enum InputKind {
+    TotpSeed = 'TotpSeed',
+    LikelyRecoveryCode = 'LikelyRecoveryCode',
+    OtpauthUri = 'OtpauthUri',
+    Unknown = 'Unknown'
+  }
+
+  /**
+   * Code valid at a future moment, with its window
+   */
+  export interface FutureCode {
+    code: string;
+
+    /** Unix time the window starts (inclusive) */
+    windowStart: number;
+
+    /** Unix time the window ends (exclusive) */
+    windowEnd: number;
+  }
+
+  /**
+   * Boundaries of a TOTP time window
+   */
+  export interface WindowBounds {
+    /** Unix time the window starts (inclusive) */
+    start: number;
+
+    /** Unix time the next window starts (exclusive) */
+    end: number;
+  }
+
+  /**
+   * Concurrency guarantee for an exported operation
+   */
+  export interface ThreadSafetyEntry {
+    operation: string;
+
+    /** Whether overlapping calls on the same object are safe */
+    concurrentSafe: boolean;
+
+    note: string;
+  }
+
+  /**
+   * Issuer and account name from an otpauth label
+   */
+  export interface OtpauthLabel {
+    issuer?: string;
+    account: string;
+  }
+
+  /**
+   * Parsed otpauth:// URI with defaults applied
+   */
+  export interface OtpauthUri {
+    /** "totp" or "hotp" */
+    otpType: string;
+
+    issuer?: string;
+
+    /** Label prefix and issuer parameter name different issuers */
+    issuerMismatch: boolean;
+
+    account: string;
+
+    /** Base32 secret, uppercase without spaces */
+    secret: string;
+
+    algorithm: string;
+    digits: number;
+    period: number;
+
+    /** Initial counter, HOTP only */
+    counter?: number;
+  }
+
+  /**
+   * Key-encryption key split across two storage locations
+   */
+  export interface KekHalves {
+    /** Store alongside the vault file */
+    fileHalf: Buffer;
+
+    /** Store in the OS keychain */
+    keychainHalf: Buffer;
+  }
+
+  /**
+   * Version and format details of the native module
+   */
+  export interface CrateInfo {
+    version: string;
+
+    /** Vault blob format versions, oldest first: 1 is salt + iv + ciphertext, 2 adds the timestamped header */
+    blobFormats: number[];
+
+    /** otpauth:// query parameters understood by the parser */
+    otpauthParameters: string[];
+
+    /** Default key derivation function, e.g. "PBKDF2-SHA256" */
+    kdf: string;
+
+    kdfIterations: number;
+    cipher: string;
+  }
+
+  /**
+   * Problem found by validate_vault_integrity
+   */
+  export interface IntegrityIssue {
+    /** Position of the account in the input list */
+    index: number;
+
+    accountId: string;
+
+    /** Field at fault, e.g. "secret" or "counter" */
+    field: string;
+
+    message: string;
+  }
+
+  /**
+   * Decrypted audit log entry
+   */
+  export interface AuditEntry {
+    event: string;
+
+    /** Timestamp supplied when the entry was appended */
+    timestamp: number;
+  }
+
+  /**
+   * Created and modified times (Unix seconds) of a timestamped vault
+   */
+  export interface VaultTimestamps {
+    created: number;
+
+    modified: number;
+  }
+
+  /**
+   * Size of each component of an encrypted blob, in bytes
+   */
+  export interface SizeBreakdown {
+    /** "v1", "timelocked", "timestamped" or "single-account" */
+    format: string;
+
+    /** Length of the base64 text */
+    encoded: number;
+
+    /** Length after base64 decoding */
+    decoded: number;
+
+    header: number;
+
+    salt: number;
+
+    iv: number;
+
+    ciphertext: number;
+
+    tag: number;
+
+    base64Overhead: number;
+  }
+
+  /**
+   * Parameters filled in for accounts of one issuer
+   */
+  export interface IssuerDefaults {
+    /** Issuer name, matched case-insensitively */
+    issuer: string;
+
+    otpType?: string;
+
+    digits?: number;
+
+    period?: number;
+
+    algorithm?: string;
+  }
+
+  /**
+   * One deduction from the vault health score
+   */
+  export interface HealthFactor {
+    /** Finding id, or NO_BACKUP / STALE_BACKUP */
+    id: string;
+
+    /** Points taken off the score */
+    penalty: number;
+
+    /** Accounts affected (0 for vault-level factors) */
+    count: number;
+
+    message: string;
+  }
+
+  /**
+   * Vault health score for the security dashboard
+   */
+  export interface HealthScore {
+    /** 0 (worst) to 100 */
+    score: number;
+
+    /** Largest penalty first */
+    factors: HealthFactor[];
+  }
+
+  /**
+   * Account records after an incremental save
+   */
+  export interface SaveReport {
+    /** One record per account, in input order */
+    records: Buffer[];
+
+    /** Account id of each record; store together with the records */
+    ids: string[];
+
+    reencrypted: number;
   }
 
   /**
@@ -39,20 +597,305 @@ declare module 'truefa-crypto-core' {
     /** SecureSecret class constructor */
     SecureSecret: typeof SecureSecret;
     
+    /** Canonical storage form of a secret: unpadded uppercase Base32 (Rust implementation) */
+    to_canonical_storage(secret: SecureSecret): string;
+
     /** Generate TOTP token (Rust implementation) */
     generate_totp(secret: SecureSecret, timestamp?: number): string;
-    
+
+    /** Format a TOTP code from an externally computed HMAC digest (Rust implementation) */
+    totp_from_hmac(hmacResult: Buffer, digits: number, algorithm: string): string;
+
+    /** Clipboard text for a code, with grouping removed and leading zeros kept (Rust implementation) */
+    copy_format(code: string): string;
+
+    /** Whether a pasted code still has all its digits, i.e. no leading zeros were lost (Rust implementation) */
+    pasted_code_intact(pasted: string, digits: number): boolean;
+
+    /**
+     * RFC 4226 dynamic truncation of an HMAC digest, before the modulo (Rust implementation)
+     * Only present in builds with the `interop` feature
+     */
+    dynamic_truncation?(hmac: Buffer): number;
+
+    /** HMAC an arbitrary challenge with the secret, returning the digest (Rust implementation) */
+    hmac_challenge_response(secret: SecureSecret, challenge: Buffer, algorithm: string): Buffer;
+
+    /** Compute an OCRA (RFC 6287) response; counter, timestamp (Unix seconds), session (hex) and PIN hash (hex) are required only when the suite uses them (Rust implementation) */
+    generate_ocra(secret: SecureSecret, suite: string, challenge: string, counter?: number, timestamp?: number, session?: string, pinHash?: string): string;
+
     /** Calculate seconds until next token (Rust implementation) */
     remaining_seconds(): number;
     
+    /** Verify a code and report the matching window offset (Rust implementation) */
+    verify_totp_verbose(secret: SecureSecret, code: string, params: TotpParams | undefined, window: number, timestamp?: number): VerifyResult;
+
+    /** Verify a code, refusing steps at or before lastAcceptedCounter; store the returned counter on success (Rust implementation) */
+    verify_totp_no_replay(secret: SecureSecret, code: string, lastAcceptedCounter: number | undefined, params: TotpParams | undefined, window: number, timestamp?: number): ReplayCheckResult;
+
+    /** Verify a code and report how long it stays valid, from one clock reading (Rust implementation) */
+    verify_and_report(secret: SecureSecret, code: string, params: TotpParams | undefined, window: number, timestamp?: number): VerifyReport;
+
+    /** Verify many codes against one clock reading; invalid entries are false (Rust implementation) */
+    verify_totp_batch(requests: VerifyRequest[], timestamp?: number): boolean[];
+
+    /** Constant-time check of a code against 2 * radius + 1 counters; returns the matching offset (Rust implementation) */
+    code_in_windows(secret: SecureSecret, code: string, centerCounter: number, radius: number, params?: TotpParams): number | null;
+
+    /** Confirm a new account with the first code the user enters, +/- 2 steps (Rust implementation) */
+    confirm_enrollment(secret: SecureSecret, userEnteredCode: string, params?: TotpParams, timestamp?: number): EnrollmentResult;
+
+    /** Whether two secrets currently show the same code within +/- window steps; an occasional coincidence is expected and harmless (Rust implementation) */
+    secrets_collide_now(secretA: SecureSecret, secretB: SecureSecret, params: TotpParams | undefined, window: number, timestamp?: number): boolean;
+
+    /** Compute the code that will be valid at a future time (Rust implementation) */
+    code_valid_at_future(secret: SecureSecret, targetTime: number, params?: TotpParams, timestamp?: number): FutureCode;
+
+    /** Compute the TOTP time step counter for a timestamp (Rust implementation) */
+    timestamp_to_counter(timestamp: number, period: number, t0: number): number;
+
+    /** Number of new codes between two Unix times, i.e. window boundaries crossed (Rust implementation) */
+    codes_until(period: number, fromTime: number, untilTime: number): number;
+
+    /** Get the TOTP window containing a timestamp (Rust implementation) */
+    window_bounds(timestamp: number, period: number, t0: number): WindowBounds;
+
     /** Validate Base32 secret format (Rust implementation) */
     validate_base32_secret(secret: string): boolean;
     
+    /** Guess whether input is a seed, recovery code or URI (Rust implementation) */
+    classify_secret_input(input: string): InputKind;
+
+    /** Split an otpauth label into issuer and account (Rust implementation) */
+    parse_otpauth_label(label: string): OtpauthLabel;
+
+    /** Parse an otpauth:// URI (Rust implementation) */
+    parse_otpauth_uri(uri: string): OtpauthUri;
+
+    /** Build an otpauth:// URI for an account (Rust implementation) */
+    build_otpauth_uri(account: Account): string;
+
+    /** Build a truefa:// QR payload with the account's otpauth URI encrypted under a PIN (at least 4 characters) (Rust implementation) */
+    build_protected_otpauth_qr(account: Account, pin: string): string;
+
+    /** Decrypt a protected truefa:// QR payload; the returned account has an empty id (Rust implementation) */
+    import_protected_qr(payload: string, pin: string): Account;
+
+    /** Smallest QR version (1-40) that fits an otpauth URI at an error correction level (Rust implementation) */
+    estimate_qr_version(uri: string, errorCorrection: 'L' | 'M' | 'Q' | 'H'): number;
+
     /** Encrypt data with AES-256-GCM (Rust implementation) */
     encrypt_data(data: string, password: string): CryptoResult;
     
     /** Decrypt data with AES-256-GCM (Rust implementation) */
     decrypt_data(encryptedData: string, password: string): CryptoResult;
+
+    /** Decrypt and reject plaintext containing NUL or other control characters (Rust implementation) */
+    decrypt_data_strict(encryptedData: string, password: string): CryptoResult;
+
+    /** DerivedKey class */
+    DerivedKey: typeof DerivedKey;
+
+    /** Encrypt a record under a key, returning nonce + ciphertext (Rust implementation) */
+    encrypt_with_key(key: DerivedKey, data: Buffer): Buffer;
+
+    /** Decrypt a record produced by encrypt_with_key (Rust implementation) */
+    decrypt_with_key(key: DerivedKey, sealed: Buffer): Buffer;
+
+    /** Encrypt each account as its own record across all CPU cores (Rust implementation) */
+    encrypt_accounts_parallel(accounts: Account[], dek: DerivedKey): Buffer[];
+
+    /** Re-encrypt only changed or new accounts, reusing the other stored records as-is (Rust implementation) */
+    save_changed_accounts(accounts: Account[], previousRecords: Buffer[], previousIds: string[], dek: DerivedKey, changedIds: string[]): SaveReport;
+
+    /** Per-record nonce: base nonce XOR record index (Rust implementation) */
+    derive_record_nonce(baseNonce: Buffer, recordIndex: number): Buffer;
+
+    /** Encrypt an envelope record at its derived nonce; returns ciphertext only (Rust implementation) */
+    encrypt_record_with_key(key: DerivedKey, baseNonce: Buffer, recordIndex: number, data: Buffer): Buffer;
+
+    /** Decrypt an envelope record encrypted with encrypt_record_with_key (Rust implementation) */
+    decrypt_record_with_key(key: DerivedKey, baseNonce: Buffer, recordIndex: number, ciphertext: Buffer): Buffer;
+
+    /** Derive the password KEK and split it into file and keychain halves (Rust implementation) */
+    split_kek_for_storage(password: string, salt: Buffer): KekHalves;
+
+    /** Rebuild a KEK from both halves; wipes the input buffers (Rust implementation) */
+    combine_kek_halves(fileHalf: Buffer, keychainHalf: Buffer): DerivedKey;
+
+    /** Derive a vault-compatible PBKDF2 key off the JS thread, calling progressCb with 0.0-1.0 as it goes; salt must be 16 bytes (Rust implementation) */
+    derive_key_with_progress(password: string, salt: Buffer, iterations: number | undefined, progressCb: (fraction: number) => void): Promise<DerivedKey>;
+
+    /** Wrap a DEK via an external key (HSM/KMS); wrapFn gets the raw key, must return synchronously and must not keep its argument (Rust implementation) */
+    wrap_dek_with_external(dek: DerivedKey, wrapFn: (dek: Buffer) => Buffer): Buffer;
+
+    /** Load a DEK wrapped by wrap_dek_with_external; unwrapFn must return the raw 32-byte key (Rust implementation) */
+    unwrap_dek_with_external(wrapped: Buffer, unwrapFn: (wrapped: Buffer) => Buffer): DerivedKey;
+
+    /**
+     * Anonymous bucket id for a failed decryption, for opt-in crash reporting (Rust implementation)
+     * Derived only from the blob's format and the error code; contains no blob data
+     */
+    decrypt_failure_signature(encryptedData: string, errorCode: string): string;
+
+    /** Byte counts of each part of an encrypted blob (header, salt, iv, ciphertext, tag) without decrypting (Rust implementation) */
+    blob_size_breakdown(encryptedData: string): SizeBreakdown;
+
+    /** Encrypt data that cannot be opened before unlockAfter (Unix seconds) (Rust implementation) */
+    encrypt_data_timelocked(data: string, password: string, unlockAfter: number): CryptoResult;
+
+    /**
+     * Decrypt time-locked data given the current time from a trusted source (Rust implementation)
+     * Only as strong as that time source; not a cryptographic time-lock
+     */
+    decrypt_data_timelocked(encryptedData: string, password: string, trustedTime: number): CryptoResult;
+
+    /** Encrypt a vault with authenticated created/modified times; pass the previous createdAt when re-saving (Rust implementation) */
+    encrypt_data_stamped(data: string, password: string, createdAt?: number, modifiedAt?: number): CryptoResult;
+
+    /** Decrypt a timestamped vault; fails if the times were edited (Rust implementation) */
+    decrypt_data_stamped(encryptedData: string, password: string): CryptoResult;
+
+    /** Read a timestamped vault's created/modified times without the password (Rust implementation) */
+    vault_timestamps(archive: string): VaultTimestamps;
+
+    /** Decrypt a vault into its accounts before switching to unencrypted storage (Rust implementation) */
+    decrypt_vault_to_plain(archive: string, password: string): Account[];
+
+    /** Serialize accounts to the labeled UNENCRYPTED vault format; warn the user first (Rust implementation) */
+    store_plain(accounts: Account[]): Buffer;
+
+    /** Read accounts from store_plain output (Rust implementation) */
+    load_plain(data: Buffer): Account[];
+
+    /** Whether file contents are an encrypted TrueFA vault; always false for unencrypted vaults and single-account backups (Rust implementation) */
+    is_truefa_blob(data: string): boolean;
+
+    /** Decrypt a ratchet log entry using the log seed (Rust implementation) */
+    decrypt_ratchet_entry(seed: Buffer, entry: Buffer): string;
+
+    /** Append an entry to an encrypted, HMAC-chained audit log; pass an empty buffer to start one (Rust implementation) */
+    append_audit_entry(logBlob: Buffer, event: string, timestamp: number, signingKey: Buffer): Buffer;
+
+    /** Check that no audit log entry was edited, removed or reordered (Rust implementation) */
+    verify_audit_log(logBlob: Buffer, signingKey: Buffer): boolean;
+
+    /** Verify and decrypt an audit log, oldest entry first (Rust implementation) */
+    read_audit_log(logBlob: Buffer, signingKey: Buffer): AuditEntry[];
+
+    /** Generate the current code for an account using its own parameters (Rust implementation) */
+    account_current_code(account: Account, timestamp?: number): string;
+
+    /** Smallest clock error (seconds) all time-based accounts tolerate (Rust implementation) */
+    max_tolerable_skew(accounts: Account[], window: number): number;
+
+    /** Count accounts whose codes a verifier at serverTime would reject (Rust implementation) */
+    estimate_failing_accounts(accounts: Account[], serverTime: number, window: number, timestamp?: number): number;
+
+    /** Current codes for all accounts grouped by category, uncategorized last; byUsage lists most-used first (Rust implementation) */
+    vault_refresh_grouped(accounts: Account[], timestamp?: number, byUsage?: boolean): CategoryGroup[];
+
+    /** Bump an account's usage metadata after a code request (Rust implementation) */
+    record_account_use(account: Account, timestamp?: number): Account;
+
+    /** Order accounts most-used first (Rust implementation) */
+    sort_by_usage(accounts: Account[]): Account[];
+
+    /** Account indices ordered soonest-to-expire first, ties by id, HOTP last (Rust implementation) */
+    sort_by_expiry(accounts: Account[], now?: number): number[];
+
+    /** Replace an account's secret, optionally keeping the old one until keepOldUntil (ms) (Rust implementation) */
+    rotate_account_secret(account: Account, newSecret: string, keepOldUntil?: number): Account;
+
+    /** Code from the previous secret during a rotation grace period, absent otherwise (Rust implementation) */
+    account_previous_code(account: Account, timestamp?: number): string | null;
+
+    /** Deterministic badge color and initials for an account (Rust implementation) */
+    account_avatar(issuer: string, account: string): AvatarInfo;
+
+    /** Encrypt a single account into a URL-safe share string (Rust implementation) */
+    create_share_payload(account: Account, password: string): string;
+
+    /** Decrypt a share string back into an account (Rust implementation) */
+    redeem_share_payload(payload: string, password: string): Account;
+
+    /** Encrypt one account, complete, as a standalone backup file (Rust implementation) */
+    export_single_account(account: Account, password: string): Buffer;
+
+    /** Read a file produced by export_single_account (Rust implementation) */
+    import_single_account(data: Buffer, password: string): Account;
+
+    /** Estimated entropy of a secret in bits; heuristic for imported secrets (Rust implementation) */
+    secret_entropy_bits(secret: SecureSecret): number;
+
+    /** Recommended minimum secret length in bytes for SHA1, SHA256 or SHA512; throws for other algorithms (Rust implementation) */
+    recommended_secret_length(algorithm: string): number;
+
+    /** Fill unset parameters of accounts from known issuers (Steam, Battle.net, plus extra entries); explicit values are kept (Rust implementation) */
+    apply_known_issuer_defaults(accounts: Account[], extra?: IssuerDefaults[]): Account[];
+
+    /** Check a decrypted vault for duplicate ids, bad secrets and invalid parameters (Rust implementation) */
+    validate_vault_integrity(accounts: Account[]): IntegrityIssue[];
+
+    /** Indices of accounts whose secret is empty or not valid Base32, for re-entry after upgrading (Rust implementation) */
+    scan_for_invalid_secrets(accounts: Account[]): number[];
+
+    /** Summarize a vault's security posture (Rust implementation) */
+    vault_security_report(accounts: Account[], blobInfo: BlobInfo): SecurityReport;
+
+    /** 0-100 health score with the factors that lowered it; lastBackup is the latest export time in ms (Rust implementation) */
+    vault_health_score(accounts: Account[], blobInfo: BlobInfo, lastBackup?: number, timestamp?: number): HealthScore;
+
+    /**
+     * Build a deterministic encrypted test vault (Rust implementation)
+     * Only present in builds with the `test-fixtures` feature
+     */
+    make_test_vault?(accountCount: number, password: string, seed: number): Buffer;
+
+    /**
+     * Derive a reproducible secret from a seed phrase and index (Rust implementation)
+     * Only present in builds with the `test-fixtures` feature
+     */
+    deterministic_secret?(seed: string, index: number): SecureSecret;
+
+    /** Split a 32-byte vault key into Shamir shares (Rust implementation) */
+    split_vault_key(dek: Buffer, threshold: number, shares: number): Buffer[];
+
+    /** Reconstruct a vault key from Shamir shares (Rust implementation) */
+    combine_vault_key(shares: Buffer[]): DerivedKey;
+
+    /** Describe which operations may run concurrently (Rust implementation) */
+    thread_safety_info(): ThreadSafetyEntry[];
+
+    /** Report the module version and supported formats (Rust implementation) */
+    crate_info(): CrateInfo;
+
+    /** Compute the changes between two account lists (Rust implementation) */
+    vault_diff(oldAccounts: Account[], newAccounts: Account[]): VaultDiff;
+
+    /** Apply a diff to the account list it was computed from (Rust implementation) */
+    apply_vault_diff(oldAccounts: Account[], diff: VaultDiff): Account[];
+
+    /** Encrypt a vault diff for sync (Rust implementation) */
+    encrypt_vault_diff(diff: VaultDiff, password: string): CryptoResult;
+
+    /** Decrypt a vault diff (Rust implementation) */
+    decrypt_vault_diff(encryptedData: string, password: string): VaultDiff;
+
+    /** Version token over an encrypted vault for sync conflict detection (Rust implementation) */
+    vault_sync_tag(encryptedVault: string): string;
+
+    /** Highest blob format version both sync peers can read (Rust implementation) */
+    negotiate_format(localMaxVersion: number, remoteMaxVersion: number): number;
+
+    /** 6-digit short authentication string for shared key material (Rust implementation) */
+    compute_sas(sharedSecret: Buffer): string;
+
+    /** Start a vault transfer on the receiving device (Rust implementation) */
+    create_transfer_offer(): TransferOffer;
+
+    /** Seal a vault for a transfer offer on the sending device (Rust implementation) */
+    accept_transfer_offer(offerPublicKey: string, vault: string, password: string): TransferAcceptance;
   }
 
   /**
@@ -98,7 +98,7 @@ debug('Native module loaded:', nativeModule ? 'SUCCESS' : 'USING FALLBACK');
 // Base32 decoding for TOTP
 function base32ToBuffer(base32) {
   const charset = 'ABCDEFGHIJKLMNOPQRSTUVWXYZ234567';
-  const cleanBase32 = base32.replace(/\s/g, '').toUpperCase();
+  const cleanBase32 = base32.replace(/[\s\u200b-\u200d\u2060]/g, '').toUpperCase();
   
   let bits = '';
   for (const char of cleanBase32) {
@@ -121,12 +121,15 @@ function base32ToBuffer(base32) {
 // A secure secret class that mimics the Rust one for the fallback
 class FallbackSecureSecret {
   constructor(base32Secret) {
-    const cleaned = base32Secret.replace(/\s/g, '').toUpperCase();
+    const cleaned = base32Secret.replace(/[\s\u200b-\u200d\u2060]/g, '').toUpperCase();
     try {
       this.inner = base32ToBuffer(cleaned);
     } catch (error) {
       throw new Error('Invalid Base32 encoding');
     }
+    if (this.inner.length === 0) {
+      throw new Error('Secret is empty');
+    }
   }
   
   clear() {
@@ -136,6 +139,7 @@ class FallbackSecureSecret {
         this.inner[i] = 0;
       }
     }
+    this.cleared = true;
   }
 }
 
@@ -157,6 +161,10 @@ function js_generateToken(secret, timestamp) {
     timeBuffer[7 - i] = (timeWindow >> (i * 8)) & 0xff;
   }
   
+  if (secret.cleared) {
+    throw new Error('Secret has been cleared');
+  }
+  
   // Create HMAC-SHA1
   const hmac = crypto.createHmac('sha1', secret.inner);
   hmac.update(timeBuffer);
@@ -193,14 +201,34 @@ function js_getRemainingTime() {
 function js_validateSecret(secret) {
   debug('Using JS fallback for secret validation');
   
-  const cleanSecret = secret.replace(/\s/g, '').toUpperCase();
+  const cleanSecret = secret.replace(/[\s\u200b-\u200d\u2060]/g, '').toUpperCase();
   return /^[A-Z2-7]+=*$/.test(cleanSecret);
 }
 
+// Same limit as the native module: longer inputs are rejected before PBKDF2
+const MAX_PASSWORD_LENGTH = 4096;
+
+function isPasswordTooLong(password) {
+  return Buffer.byteLength(password, 'utf8') > MAX_PASSWORD_LENGTH;
+}
+
+function passwordTooLongResult() {
+  return {
+    data: '',
+    success: false,
+    error: `Password is too long: at most ${MAX_PASSWORD_LENGTH} bytes are supported`,
+    errorCode: 'PASSWORD_TOO_LONG'
+  };
+}
+
 // JS Implementation of data encryption
 function js_encrypt(data, password) {
   debug('Using JS fallback for encryption');
   
+  if (isPasswordTooLong(password)) {
+    return passwordTooLongResult();
+  }
+  
   try {
     // Generate secure random values
     const salt = crypto.randomBytes(16);
@@ -245,7 +273,8 @@ function js_encrypt(data, password) {
     return {
       data: '',
       success: false,
-      error: `Encryption failed: ${error.message}`
+      error: `Encryption failed: ${error.message}`,
+      errorCode: 'ENCRYPTION_FAILED'
     };
   }
 }
@@ -254,10 +283,22 @@ function js_encrypt(data, password) {
 function js_decrypt(encryptedData, password) {
   debug('Using JS fallback for decryption');
   
+  if (isPasswordTooLong(password)) {
+    return passwordTooLongResult();
+  }
+  
   try {
     // Decode base64 data
     const data = Buffer.from(encryptedData, 'base64');
     
+    // Salt + iv + tag is the shortest possible blob
+    if (data.length < 28) {
+      return { data: '', success: false, error: 'Invalid encrypted data format', errorCode: 'INVALID_FORMAT' };
+    }
+    if (data.length < 44) {
+      return { data: '', success: false, error: 'Encrypted data is truncated', errorCode: 'TRUNCATED' };
+    }
+    
     // Extract components
     const salt = data.slice(0, 16);
     const iv = data.slice(16, 28);
@@ -300,8 +341,9 @@ function js_decrypt(encryptedData, password) {
       data: '',
       success: false,
       error: isAuthError 
-        ? 'Decryption failed: wrong password'
-        : `Decryption failed: ${error.message}`
+        ? 'Decryption failed: wrong password or corrupted data'
+        : `Decryption failed: ${error.message}`,
+      errorCode: isAuthError ? 'AUTHENTICATION_FAILED' : 'INVALID_FORMAT'
     };
   }
 }
@@ -0,0 +1,702 @@
+// Account records as stored in the app's vault, plus single-account sharing and backup
+
+use std::collections::BTreeMap;
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::{
+    current_timestamp, decode_secret, decrypt_bytes, decrypt_bytes_bound, normalize_base32, encrypt_bytes,
+    encrypt_bytes_bound, hotp_code, steam_code, time_counter, ResolvedParams, TotpParams, TOTP_PERIOD,
+};
+
+// Single-account backup file layout: magic (4 bytes) + the usual salt + iv +
+// ciphertext of the account's JSON; the magic is bound as AAD, so the file
+// cannot be passed off as a full vault or the other way round
+pub(crate) const SINGLE_ACCOUNT_MAGIC: &[u8; 4] = b"TFA1";
+
+// One authenticator account, matching the frontend's `AuthAccount` JSON shape
+#[napi(object)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub id: String,
+    pub name: String,
+    pub issuer: String,
+    // Base32 encoded secret key
+    pub secret: String,
+    // Unix time of creation in milliseconds
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digits: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<u32>,
+    // "totp" (default), "hotp" or "steam"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp_type: Option<String>,
+    // Next HOTP counter value; required for "hotp" accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<i64>,
+    // Unix time of the last code request in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<i64>,
+    // Number of code requests; stored in the encrypted vault like every other field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_count: Option<u32>,
+    // Folder the account is filed under; uncategorized when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    // Secret replaced by `rotate_account_secret`, kept while the service may still expect it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_secret: Option<String>,
+    // Unix time in milliseconds until which `previous_secret` is still used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_secret_until: Option<i64>,
+}
+
+impl Account {
+    // Current code for this account, using its own type and parameters
+    pub fn current_code(&self, timestamp: Option<i64>) -> napi::Result<String> {
+        let mut secret = decode_secret(&self.secret)?;
+        let result = self.code_for_secret(&secret, timestamp);
+        secret.zeroize();
+        result
+    }
+
+    fn code_for_secret(&self, secret: &[u8], timestamp: Option<i64>) -> napi::Result<String> {
+        let time = timestamp.unwrap_or_else(current_timestamp);
+        let otp_type = self.otp_type.as_deref().unwrap_or("totp").to_lowercase();
+
+        match otp_type.as_str() {
+            "totp" => {
+                let params = ResolvedParams::resolve(Some(self.params()))?;
+                let counter = time_counter(time, params.period, params.t0)?;
+                hotp_code(secret, counter as u64, params.digits, params.algorithm)
+            }
+            "hotp" => {
+                let params = ResolvedParams::resolve(Some(self.params()))?;
+                let counter = self
+                    .counter
+                    .filter(|counter| *counter >= 0)
+                    .ok_or_else(|| napi::Error::from_reason("HOTP account is missing a valid counter"))?;
+                hotp_code(secret, counter as u64, params.digits, params.algorithm)
+            }
+            "steam" => {
+                let counter = time_counter(time, TOTP_PERIOD as u32, 0)?;
+                steam_code(secret, counter as u64)
+            }
+            other => Err(napi::Error::from_reason(format!("Unsupported OTP type: {}", other))),
+        }
+    }
+
+    // Code from the secret replaced by a rotation, while its grace period lasts
+    pub fn previous_code(&self, timestamp: Option<i64>) -> napi::Result<Option<String>> {
+        let time = timestamp.unwrap_or_else(current_timestamp);
+        let previous = match (&self.previous_secret, self.previous_secret_until) {
+            (Some(previous), Some(until)) if time.saturating_mul(1000) <= until => previous,
+            _ => return Ok(None),
+        };
+
+        let mut secret = decode_secret(previous)?;
+        let result = self.code_for_secret(&secret, Some(time));
+        secret.zeroize();
+        result.map(Some)
+    }
+
+    // Time step in seconds for time-based accounts, None for HOTP
+    pub(crate) fn time_step(&self) -> napi::Result<Option<u32>> {
+        match self.otp_type.as_deref().unwrap_or("totp").to_lowercase().as_str() {
+            "totp" => Ok(Some(ResolvedParams::resolve(Some(self.params()))?.period)),
+            "steam" => Ok(Some(TOTP_PERIOD as u32)),
+            "hotp" => Ok(None),
+            other => Err(napi::Error::from_reason(format!("Unsupported OTP type: {}", other))),
+        }
+    }
+
+    // The account's TOTP/HOTP parameters; unset fields fall back to defaults
+    pub(crate) fn params(&self) -> TotpParams {
+        TotpParams {
+            digits: self.digits,
+            period: self.period,
+            algorithm: self.algorithm.clone(),
+            t0: None,
+        }
+    }
+}
+
+// Generate the current code for an account, dispatching on its OTP type
+#[napi]
+pub fn account_current_code(account: Account, timestamp: Option<i64>) -> napi::Result<String> {
+    account.current_code(timestamp)
+}
+
+// Current code of one account inside a category group
+#[napi(object)]
+pub struct AccountCode {
+    pub id: String,
+    pub name: String,
+    pub issuer: String,
+    // Absent when the code could not be generated, with `error` saying why
+    pub code: Option<String>,
+    pub error: Option<String>,
+}
+
+// Accounts of one category with their current codes, sorted by issuer then name
+// (or most-used first when requested)
+#[napi(object)]
+pub struct CategoryGroup {
+    // None for accounts without a category
+    pub category: Option<String>,
+    pub accounts: Vec<AccountCode>,
+}
+
+// Current codes for all accounts, grouped by category
+// Groups are ordered by name with the uncategorized group last. With `by_usage`
+// each group lists its accounts in `sort_by_usage` order, equally used ones
+// alphabetically. One broken account reports its error in place rather than
+// failing the whole refresh.
+#[napi]
+pub fn vault_refresh_grouped(accounts: Vec<Account>, timestamp: Option<i64>, by_usage: Option<bool>) -> Vec<CategoryGroup> {
+    let time = timestamp.unwrap_or_else(current_timestamp);
+
+    let mut ordered: Vec<&Account> = accounts.iter().collect();
+    ordered.sort_by_cached_key(|account| (account.issuer.to_lowercase(), account.name.to_lowercase()));
+    if by_usage.unwrap_or(false) {
+        // Stable, so the alphabetical order above breaks ties
+        ordered.sort_by_key(|account| usage_rank(account));
+    }
+
+    let mut groups: BTreeMap<(bool, String), CategoryGroup> = BTreeMap::new();
+    for account in ordered {
+        let category = account
+            .category
+            .as_deref()
+            .map(str::trim)
+            .filter(|category| !category.is_empty());
+        // Sort key: uncategorized after every named group
+        let key = (category.is_none(), category.unwrap_or_default().to_lowercase());
+        let group = groups.entry(key).or_insert_with(|| CategoryGroup {
+            category: category.map(str::to_string),
+            accounts: Vec::new(),
+        });
+
+        let (code, error) = match account.current_code(Some(time)) {
+            Ok(code) => (Some(code), None),
+            Err(error) => (None, Some(error.reason)),
+        };
+        group.accounts.push(AccountCode {
+            id: account.id.clone(),
+            name: account.name.clone(),
+            issuer: account.issuer.clone(),
+            code,
+            error,
+        });
+    }
+
+    groups.into_values().collect()
+}
+
+// Record that the user requested a code for `account`; save the result with the vault
+// `timestamp` is in milliseconds like `created_at` and defaults to now
+#[napi]
+pub fn record_account_use(mut account: Account, timestamp: Option<i64>) -> Account {
+    account.last_used = Some(timestamp.unwrap_or_else(|| current_timestamp() * 1000));
+    account.use_count = Some(account.use_count.unwrap_or(0).saturating_add(1));
+    account
+}
+
+// Replace an account's secret after the user re-enrolled 2FA with the service,
+// keeping its id, history and every other field
+// With `keep_old_until` (Unix time in milliseconds) the old secret is kept until
+// then and `account_previous_code` keeps producing its codes, for services that
+// accept the old secret until the new one is confirmed. Without it the old
+// secret is dropped immediately, together with any earlier one still kept.
+#[napi]
+pub fn rotate_account_secret(mut account: Account, new_secret: String, keep_old_until: Option<i64>) -> napi::Result<Account> {
+    let mut new_bytes = decode_secret(&new_secret)?;
+    let mut old_bytes = decode_secret(&account.secret).ok();
+    let unchanged = old_bytes.as_deref() == Some(new_bytes.as_slice());
+    new_bytes.zeroize();
+    if let Some(bytes) = old_bytes.as_mut() {
+        bytes.zeroize();
+    }
+    if unchanged {
+        return Err(napi::Error::from_reason("New secret is the same as the current one"));
+    }
+
+    if let Some(mut previous) = account.previous_secret.take() {
+        previous.zeroize();
+    }
+    let canonical = normalize_base32(&new_secret).trim_end_matches('=').to_string();
+    let mut old_secret = std::mem::replace(&mut account.secret, canonical);
+    if keep_old_until.is_some() {
+        account.previous_secret = Some(old_secret);
+    } else {
+        old_secret.zeroize();
+    }
+    account.previous_secret_until = keep_old_until;
+    Ok(account)
+}
+
+// Code from an account's previous secret while its rotation grace period lasts;
+// absent when there is none or it has expired
+#[napi]
+pub fn account_previous_code(account: Account, timestamp: Option<i64>) -> napi::Result<Option<String>> {
+    account.previous_code(timestamp)
+}
+
+// Order accounts most-used first, breaking ties by most recent use
+// Accounts that were never used keep their relative order at the end
+#[napi]
+pub fn sort_by_usage(mut accounts: Vec<Account>) -> Vec<Account> {
+    accounts.sort_by_key(usage_rank);
+    accounts
+}
+
+fn usage_rank(account: &Account) -> std::cmp::Reverse<(u32, i64)> {
+    std::cmp::Reverse((account.use_count.unwrap_or(0), account.last_used.unwrap_or(0)))
+}
+
+// Indices of `accounts` ordered by seconds left in their current time step,
+// soonest to expire first; ties go to the smaller account id
+// The clock is read once (`now`, Unix seconds, defaults to the current time),
+// so every account is measured at the same instant. HOTP accounts never expire
+// and come last, also ordered by id.
+#[napi]
+pub fn sort_by_expiry(accounts: Vec<Account>, now: Option<i64>) -> napi::Result<Vec<u32>> {
+    let time = now.unwrap_or_else(current_timestamp);
+    let mut keyed = accounts
+        .iter()
+        .enumerate()
+        .map(|(index, account)| {
+            let remaining = account
+                .time_step()?
+                .map(|period| period as i64 - time.rem_euclid(period as i64));
+            Ok((remaining.is_none(), remaining, account.id.as_str(), index as u32))
+        })
+        .collect::<napi::Result<Vec<_>>>()?;
+    keyed.sort();
+    Ok(keyed.into_iter().map(|(_, _, _, index)| index).collect())
+}
+
+// Badge shown next to an account in the list
+#[napi(object)]
+pub struct AvatarInfo {
+    // "#rrggbb"
+    pub color_hex: String,
+    // One or two uppercase letters, or "?" when there is nothing to take them from
+    pub initials: String,
+}
+
+// Deterministic badge for an account, identical on every device
+// The color comes from a hash of the issuer (case-insensitive), so all accounts of
+// one service share it; the initials come from the account name, falling back to
+// the issuer. Only the part of an email address before the '@' is used.
+#[napi]
+pub fn account_avatar(issuer: String, account: String) -> AvatarInfo {
+    let issuer = issuer.trim();
+    let account = account.trim();
+
+    let color_source = if issuer.is_empty() { account } else { issuer };
+    let color_hex = if color_source.is_empty() {
+        AVATAR_FALLBACK_COLOR.to_string()
+    } else {
+        let digest = Sha256::digest(color_source.to_lowercase().as_bytes());
+        let hue = u16::from_be_bytes([digest[0], digest[1]]) % 360;
+        hsl_to_hex(hue as f64, AVATAR_SATURATION, AVATAR_LIGHTNESS)
+    };
+
+    let name = account.split('@').next().unwrap_or_default();
+    let initials = [name, issuer]
+        .iter()
+        .map(|source| initials_of(source))
+        .find(|initials| !initials.is_empty())
+        .unwrap_or_else(|| "?".to_string());
+
+    AvatarInfo { color_hex, initials }
+}
+
+const AVATAR_FALLBACK_COLOR: &str = "#808080";
+// Mid saturation and lightness keep white initials readable on every hue
+const AVATAR_SATURATION: f64 = 0.65;
+const AVATAR_LIGHTNESS: f64 = 0.45;
+
+// First letter of each of the first two words, splitting on anything that is not
+// a letter or digit (so "john.doe" gives "JD" and "東京" gives "東")
+fn initials_of(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+// Encrypt one account into a compact, URL-safe string for sending over a messaging app
+// The password is not part of the payload and must be shared out-of-band
+// (e.g. read aloud over a call), never in the same message as the payload
+#[napi]
+pub fn create_share_payload(mut account: Account, password: String) -> napi::Result<String> {
+    // Usage history is personal and meaningless to the recipient
+    account.last_used = None;
+    account.use_count = None;
+    // A retired secret must not travel with the account
+    if let Some(mut previous) = account.previous_secret.take() {
+        previous.zeroize();
+    }
+    account.previous_secret_until = None;
+
+    let mut json = serde_json::to_vec(&account)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+    let result = encrypt_bytes(&json, &password);
+    json.zeroize();
+
+    result
+        .map(|sealed| base64::encode_config(&sealed, base64::URL_SAFE_NO_PAD))
+        .map_err(napi::Error::from)
+}
+
+// Decrypt a payload produced by `create_share_payload`
+#[napi]
+pub fn redeem_share_payload(payload: String, password: String) -> napi::Result<Account> {
+    let sealed = base64::decode_config(payload.trim(), base64::URL_SAFE_NO_PAD)
+        .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+    let mut json = decrypt_bytes(&sealed, &password)?;
+    let account = serde_json::from_slice(&json)
+        .map_err(|e| napi::Error::from_reason(format!("Invalid account data: {}", e)));
+    json.zeroize();
+
+    account
+}
+
+// Encrypt one account as a standalone backup file
+// Unlike a share payload, the account is kept whole, usage history and any
+// rotation grace secret included, so importing it restores the same state.
+// The returned bytes are the file contents.
+#[napi]
+pub fn export_single_account(account: Account, password: String) -> napi::Result<Buffer> {
+    seal_single_account(&account, &password).map(Buffer::from)
+}
+
+// Read a file produced by `export_single_account`
+#[napi]
+pub fn import_single_account(data: Buffer, password: String) -> napi::Result<Account> {
+    open_single_account(&data, &password)
+}
+
+fn seal_single_account(account: &Account, password: &str) -> napi::Result<Vec<u8>> {
+    let mut json = serde_json::to_vec(account)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+    let result = encrypt_bytes_bound(&json, password, SINGLE_ACCOUNT_MAGIC);
+    json.zeroize();
+
+    let sealed = result?;
+    Ok([SINGLE_ACCOUNT_MAGIC.as_slice(), &sealed].concat())
+}
+
+fn open_single_account(data: &[u8], password: &str) -> napi::Result<Account> {
+    let sealed = data
+        .strip_prefix(SINGLE_ACCOUNT_MAGIC.as_slice())
+        .ok_or_else(|| napi::Error::from_reason("Not a single-account backup"))?;
+    let mut json = decrypt_bytes_bound(sealed, password, SINGLE_ACCOUNT_MAGIC)?;
+    let account = serde_json::from_slice(&json)
+        .map_err(|e| napi::Error::from_reason(format!("Invalid account data: {}", e)));
+    json.zeroize();
+
+    account
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(id: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: "alice@example.com".to_string(),
+            issuer: "Example".to_string(),
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            created_at: 1_700_000_000_000,
+            ..Default::default()
+        }
+    }
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn current_code_for_each_otp_type() {
+        let totp = account("totp");
+        assert_eq!(totp.current_code(Some(NOW)).unwrap(), "324550");
+
+        let mut steam = account("steam");
+        steam.otp_type = Some("Steam".to_string());
+        assert_eq!(steam.current_code(Some(NOW)).unwrap(), "2KM2P");
+
+        // HOTP ignores the time and uses the stored counter
+        let mut hotp = account("hotp");
+        hotp.otp_type = Some("hotp".to_string());
+        hotp.counter = Some(7);
+        assert_eq!(hotp.current_code(Some(NOW)).unwrap(), "449891");
+        assert_eq!(hotp.current_code(Some(0)).unwrap(), "449891");
+    }
+
+    #[test]
+    fn current_code_rejects_bad_accounts() {
+        let mut hotp = account("hotp");
+        hotp.otp_type = Some("hotp".to_string());
+        assert!(hotp.current_code(Some(NOW)).is_err());
+        hotp.counter = Some(-1);
+        assert!(hotp.current_code(Some(NOW)).is_err());
+
+        let mut unknown = account("x");
+        unknown.otp_type = Some("motp".to_string());
+        assert_eq!(unknown.current_code(Some(NOW)).unwrap_err().reason, "Unsupported OTP type: motp");
+    }
+
+    fn used(id: &str, issuer: &str, use_count: Option<u32>, last_used: Option<i64>) -> Account {
+        Account {
+            issuer: issuer.to_string(),
+            use_count,
+            last_used,
+            ..account(id)
+        }
+    }
+
+    fn refreshed_ids(accounts: Vec<Account>, by_usage: Option<bool>) -> Vec<Vec<String>> {
+        vault_refresh_grouped(accounts, Some(NOW), by_usage)
+            .into_iter()
+            .map(|group| group.accounts.into_iter().map(|entry| entry.id).collect())
+            .collect()
+    }
+
+    #[test]
+    fn refresh_groups_by_category_with_uncategorized_last() {
+        let filed = |id: &str, category: Option<&str>| Account {
+            category: category.map(str::to_string),
+            ..account(id)
+        };
+        let accounts = vec![
+            filed("none", None),
+            filed("blank", Some("  ")),
+            filed("work", Some("Work")),
+            filed("bank", Some("banking")),
+            filed("work-2", Some(" work ")),
+        ];
+
+        let groups = vault_refresh_grouped(accounts, Some(NOW), None);
+        let categories: Vec<Option<&str>> = groups.iter().map(|group| group.category.as_deref()).collect();
+        assert_eq!(categories, vec![Some("banking"), Some("Work"), None]);
+        assert_eq!(groups[1].accounts.len(), 2);
+        assert_eq!(groups[2].accounts.len(), 2);
+        let codes: Vec<Option<&str>> = groups
+            .iter()
+            .flat_map(|group| group.accounts.iter().map(|entry| entry.code.as_deref()))
+            .collect();
+        assert_eq!(codes, vec![Some("324550"); 5]);
+    }
+
+    #[test]
+    fn refresh_reports_broken_accounts_in_place() {
+        let mut broken = account("broken");
+        broken.secret = "not base32!".to_string();
+        let groups = vault_refresh_grouped(vec![broken, account("ok")], Some(NOW), None);
+
+        assert_eq!(groups.len(), 1);
+        let entries = &groups[0].accounts;
+        assert_eq!(entries.len(), 2);
+        let broken = entries.iter().find(|entry| entry.id == "broken").unwrap();
+        assert!(broken.code.is_none());
+        assert!(broken.error.is_some());
+        let ok = entries.iter().find(|entry| entry.id == "ok").unwrap();
+        assert_eq!((ok.code.as_deref(), ok.error.as_deref()), (Some("324550"), None));
+    }
+
+    #[test]
+    fn record_account_use_increments() {
+        let once = record_account_use(account("a"), Some(1_700_000_000_000));
+        assert_eq!((once.use_count, once.last_used), (Some(1), Some(1_700_000_000_000)));
+
+        let twice = record_account_use(once, Some(1_700_000_060_000));
+        assert_eq!((twice.use_count, twice.last_used), (Some(2), Some(1_700_000_060_000)));
+    }
+
+    #[test]
+    fn refresh_sorts_by_usage_on_request() {
+        let mut work = used("work", "Zeta", Some(1), Some(5));
+        work.category = Some("Work".to_string());
+        let accounts = vec![
+            used("never", "Alpha", None, None),
+            used("often", "Mid", Some(9), Some(1)),
+            used("recent", "Beta", Some(3), Some(20)),
+            used("older", "Gamma", Some(3), Some(10)),
+            work,
+        ];
+
+        // Alphabetical by default, uncategorized last
+        assert_eq!(
+            refreshed_ids(accounts.clone(), None),
+            vec![vec!["work"], vec!["never", "recent", "older", "often"]]
+        );
+        assert_eq!(refreshed_ids(accounts.clone(), Some(false)), refreshed_ids(accounts.clone(), None));
+        // Most used first, ties by most recent use, never-used accounts last
+        assert_eq!(
+            refreshed_ids(accounts, Some(true)),
+            vec![vec!["work"], vec!["often", "recent", "older", "never"]]
+        );
+    }
+
+    #[test]
+    fn refresh_by_usage_keeps_unused_accounts_alphabetical() {
+        let accounts = vec![
+            used("c", "Charlie", None, None),
+            used("a", "alpha", None, None),
+            used("b", "Bravo", None, None),
+        ];
+        assert_eq!(refreshed_ids(accounts, Some(true)), vec![vec!["a", "b", "c"]]);
+    }
+
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn rotation_keeps_identity_and_the_old_secret_for_the_grace_period() {
+        let mut original = account("a");
+        original.category = Some("Work".to_string());
+        original.use_count = Some(3);
+        let until = (NOW + 60) * 1000;
+        let new_secret = "gezd gnbv gy3t qojq gezd gnbv gy3t qojq".to_string();
+        let rotated = rotate_account_secret(original.clone(), new_secret, Some(until)).unwrap();
+
+        assert_eq!(rotated.secret, RFC_SECRET);
+        assert_eq!((rotated.id.as_str(), rotated.created_at), ("a", original.created_at));
+        assert_eq!((rotated.category.as_deref(), rotated.use_count), (Some("Work"), Some(3)));
+
+        let fresh = Account {
+            secret: RFC_SECRET.to_string(),
+            ..account("b")
+        };
+        assert_eq!(rotated.current_code(Some(NOW)).unwrap(), fresh.current_code(Some(NOW)).unwrap());
+        // The old secret's code until the grace period ends, then nothing
+        assert_eq!(rotated.previous_code(Some(NOW)).unwrap().as_deref(), Some("324550"));
+        assert!(rotated.previous_code(Some(NOW + 60)).unwrap().is_some());
+        assert_eq!(rotated.previous_code(Some(NOW + 61)).unwrap(), None);
+    }
+
+    #[test]
+    fn rotation_without_grace_drops_old_secrets() {
+        let kept = rotate_account_secret(account("a"), RFC_SECRET.to_string(), Some((NOW + 60) * 1000)).unwrap();
+        let rotated = rotate_account_secret(kept, "MFRGGZDFMZTWQ2LK".to_string(), None).unwrap();
+        assert_eq!(rotated.previous_secret, None);
+        assert_eq!(rotated.previous_secret_until, None);
+        assert_eq!(rotated.previous_code(Some(NOW)).unwrap(), None);
+    }
+
+    #[test]
+    fn rotation_rejects_the_same_or_an_invalid_secret() {
+        let same = rotate_account_secret(account("a"), "jbsw y3dp ehpk 3pxp".to_string(), None);
+        assert_eq!(same.unwrap_err().reason, "New secret is the same as the current one");
+        assert!(rotate_account_secret(account("a"), "not base32!".to_string(), None).is_err());
+        assert!(rotate_account_secret(account("a"), String::new(), None).is_err());
+    }
+
+    fn timed(id: &str, period: Option<u32>, otp_type: Option<&str>) -> Account {
+        Account {
+            period,
+            otp_type: otp_type.map(str::to_string),
+            counter: Some(0),
+            ..account(id)
+        }
+    }
+
+    #[test]
+    fn sort_by_expiry_accounts_for_each_period() {
+        // At NOW: 10s left of a 30s step, 40s of a 60s step, 10s of a 15s step
+        let accounts = vec![
+            timed("hotp", None, Some("hotp")),
+            timed("sixty", Some(60), None),
+            timed("thirty", None, None),
+            timed("fifteen", Some(15), None),
+            timed("steam", None, Some("steam")),
+        ];
+        // Ties by id, HOTP last
+        assert_eq!(sort_by_expiry(accounts.clone(), Some(NOW)).unwrap(), vec![3, 4, 2, 1, 0]);
+        // 12 seconds later: 13s left of the 15s step, 28s of the others
+        assert_eq!(sort_by_expiry(accounts, Some(NOW + 12)).unwrap(), vec![3, 1, 4, 2, 0]);
+    }
+
+    #[test]
+    fn sort_by_expiry_rejects_unknown_types() {
+        assert!(sort_by_expiry(vec![timed("x", None, Some("motp"))], Some(NOW)).is_err());
+        assert!(sort_by_expiry(Vec::new(), Some(NOW)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn share_payload_round_trip() {
+        let mut shared = account("a");
+        shared.digits = Some(8);
+        shared.last_used = Some(1_700_000_100_000);
+        shared.use_count = Some(4);
+
+        let payload = create_share_payload(shared.clone(), "hunter2".to_string()).unwrap();
+        let redeemed = redeem_share_payload(payload, "hunter2".to_string()).unwrap();
+
+        // Usage history stays with the sender
+        shared.last_used = None;
+        shared.use_count = None;
+        assert_eq!(redeemed, shared);
+    }
+
+    #[test]
+    fn share_payload_needs_the_password() {
+        let payload = create_share_payload(account("a"), "hunter2".to_string()).unwrap();
+        assert!(redeem_share_payload(payload, "hunter3".to_string()).is_err());
+    }
+
+    #[test]
+    fn single_account_backup_round_trip() {
+        let mut backed_up = account("a");
+        backed_up.use_count = Some(4);
+        backed_up.previous_secret = Some("GEZDGNBVGY3TQOJQ".to_string());
+
+        let file = seal_single_account(&backed_up, "hunter2").unwrap();
+        assert!(file.starts_with(SINGLE_ACCOUNT_MAGIC));
+
+        // Usage history and the grace secret come back too
+        assert_eq!(open_single_account(&file, "hunter2").unwrap(), backed_up);
+        assert!(open_single_account(&file, "hunter3").is_err());
+    }
+
+    #[test]
+    fn single_account_backup_is_not_a_vault() {
+        let file = seal_single_account(&account("a"), "hunter2").unwrap();
+        assert!(!crate::decrypt_data(base64::encode(&file), "hunter2".to_string()).success);
+        assert!(!crate::plain::is_truefa_blob(base64::encode(&file)));
+
+        // ... and a vault is not a single-account backup, even behind the magic
+        let json = serde_json::to_string(&account("a")).unwrap();
+        let vault = base64::decode(crate::encrypt_data(json, "hunter2".to_string()).data).unwrap();
+        assert!(open_single_account(&vault, "hunter2").is_err());
+        let relabeled = [SINGLE_ACCOUNT_MAGIC.as_slice(), &vault].concat();
+        assert!(open_single_account(&relabeled, "hunter2").is_err());
+    }
+}
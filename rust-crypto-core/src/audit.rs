@@ -0,0 +1,324 @@
+// Encrypted, append-only audit log of 2FA access for compliance
+//
+// Every entry is encrypted and carries an HMAC over its own bytes and the
+// previous entry's HMAC, so editing, removing or reordering any entry breaks
+// the chain from that point on. Both keys are derived from one signing key.
+// Log layout: magic (4 bytes) followed by entries of
+// length (4 bytes, big-endian, of iv + ciphertext) + iv + ciphertext + HMAC (32 bytes).
+// The plaintext of an entry is its timestamp (8 bytes, big-endian) + the event text.
+//
+// Limits: dropping entries from the end leaves a valid, shorter log. Callers that
+// need to detect this must keep the entry count somewhere the log cannot be edited.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use hmac::{Hmac, Mac};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::{hkdf_sha256, IV_LENGTH};
+
+const AUDIT_MAGIC: &[u8; 4] = b"TFL1";
+const SIGNING_KEY_LENGTH: usize = 32;
+const LENGTH_PREFIX: usize = 4;
+const MAC_LENGTH: usize = 32;
+
+// Separate HKDF labels so the encryption and chaining keys never coincide
+const ENCRYPTION_KEY_INFO: &[u8] = b"truefa-audit-encryption";
+const MAC_KEY_INFO: &[u8] = b"truefa-audit-mac";
+
+// One decrypted audit log entry
+#[napi(object)]
+pub struct AuditEntry {
+    pub event: String,
+    pub timestamp: i64,
+}
+
+// Append an entry to an audit log and return the extended log
+// Pass an empty buffer to start a new log. The existing chain is verified first,
+// so a tampered log is never extended.
+#[napi]
+pub fn append_audit_entry(log_blob: Buffer, event: String, timestamp: i64, signing_key: Buffer) -> napi::Result<Buffer> {
+    let keys = AuditKeys::derive(&signing_key)?;
+    append_entry(&log_blob, &event, timestamp, &keys).map(Buffer::from)
+}
+
+// Check that every entry of an audit log is intact and in its original order
+#[napi]
+pub fn verify_audit_log(log_blob: Buffer, signing_key: Buffer) -> napi::Result<bool> {
+    let keys = AuditKeys::derive(&signing_key)?;
+    Ok(verified_entries(&log_blob, &keys).is_ok())
+}
+
+// Verify and decrypt an audit log, oldest entry first
+#[napi]
+pub fn read_audit_log(log_blob: Buffer, signing_key: Buffer) -> napi::Result<Vec<AuditEntry>> {
+    let keys = AuditKeys::derive(&signing_key)?;
+    read_entries(&log_blob, &keys)
+}
+
+fn append_entry(log_blob: &[u8], event: &str, timestamp: i64, keys: &AuditKeys) -> napi::Result<Vec<u8>> {
+    let (entries, previous_mac) = if log_blob.is_empty() {
+        (Vec::new(), [0u8; MAC_LENGTH])
+    } else {
+        verified_entries(log_blob, keys)?
+    };
+    let index = u32::try_from(entries.len()).map_err(|_| napi::Error::from_reason("Audit log is full"))?;
+
+    let mut iv = [0u8; IV_LENGTH];
+    SystemRandom::new()
+        .fill(&mut iv)
+        .map_err(|_| napi::Error::from_reason("Failed to generate nonce"))?;
+
+    let mut plaintext = Vec::with_capacity(8 + event.len());
+    plaintext.extend_from_slice(&timestamp.to_be_bytes());
+    plaintext.extend_from_slice(event.as_bytes());
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&keys.encryption));
+    let result = cipher.encrypt(
+        Nonce::from_slice(&iv),
+        Payload {
+            msg: &plaintext,
+            aad: &entry_aad(index, &previous_mac),
+        },
+    );
+    plaintext.zeroize();
+    let ciphertext = result.map_err(|e| napi::Error::from_reason(format!("Encryption error: {}", e)))?;
+
+    let mut log = if log_blob.is_empty() {
+        AUDIT_MAGIC.to_vec()
+    } else {
+        log_blob.to_vec()
+    };
+    let body_start = log.len();
+    log.extend_from_slice(&((IV_LENGTH + ciphertext.len()) as u32).to_be_bytes());
+    log.extend_from_slice(&iv);
+    log.extend_from_slice(&ciphertext);
+    let mac = entry_mac(keys, &previous_mac, index, &log[body_start..])?;
+    log.extend_from_slice(&mac);
+    Ok(log)
+}
+
+fn read_entries(log_blob: &[u8], keys: &AuditKeys) -> napi::Result<Vec<AuditEntry>> {
+    let (entries, _) = verified_entries(log_blob, keys)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&keys.encryption));
+
+    entries
+        .iter()
+        .map(|entry| {
+            let mut plaintext = cipher
+                .decrypt(
+                    Nonce::from_slice(entry.iv),
+                    Payload {
+                        msg: entry.ciphertext,
+                        aad: &entry_aad(entry.index, &entry.previous_mac),
+                    },
+                )
+                .map_err(|_| napi::Error::from_reason("Audit entry failed authentication"))?;
+            if plaintext.len() < 8 {
+                plaintext.zeroize();
+                return Err(napi::Error::from_reason("Invalid audit entry format"));
+            }
+            let mut timestamp = [0u8; 8];
+            timestamp.copy_from_slice(&plaintext[..8]);
+            let event = String::from_utf8(plaintext.split_off(8))
+                .map_err(|e| napi::Error::from_reason(format!("UTF-8 decode error: {}", e)))?;
+            Ok(AuditEntry {
+                event,
+                timestamp: i64::from_be_bytes(timestamp),
+            })
+        })
+        .collect()
+}
+
+struct AuditKeys {
+    encryption: [u8; 32],
+    mac: [u8; 32],
+}
+
+impl AuditKeys {
+    fn derive(signing_key: &[u8]) -> napi::Result<Self> {
+        if signing_key.len() != SIGNING_KEY_LENGTH {
+            return Err(napi::Error::from_reason(format!(
+                "Invalid audit signing key: expected {} bytes",
+                SIGNING_KEY_LENGTH
+            )));
+        }
+        let mut keys = Self {
+            encryption: [0u8; 32],
+            mac: [0u8; 32],
+        };
+        hkdf_sha256(signing_key, &[], ENCRYPTION_KEY_INFO, &mut keys.encryption)?;
+        hkdf_sha256(signing_key, &[], MAC_KEY_INFO, &mut keys.mac)?;
+        Ok(keys)
+    }
+}
+
+impl Drop for AuditKeys {
+    fn drop(&mut self) {
+        self.encryption.zeroize();
+        self.mac.zeroize();
+    }
+}
+
+// An entry whose HMAC has been checked, borrowed from the log
+struct ChainedEntry<'a> {
+    index: u32,
+    previous_mac: [u8; MAC_LENGTH],
+    iv: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+// Walk the chain, checking every HMAC; returns the entries and the last HMAC
+fn verified_entries<'a>(log: &'a [u8], keys: &AuditKeys) -> napi::Result<(Vec<ChainedEntry<'a>>, [u8; MAC_LENGTH])> {
+    let invalid = || napi::Error::from_reason("Invalid audit log format");
+    if log.len() < AUDIT_MAGIC.len() || &log[..AUDIT_MAGIC.len()] != AUDIT_MAGIC {
+        return Err(invalid());
+    }
+
+    let mut entries = Vec::new();
+    let mut previous_mac = [0u8; MAC_LENGTH];
+    let mut rest = &log[AUDIT_MAGIC.len()..];
+    while !rest.is_empty() {
+        let length = rest
+            .get(..LENGTH_PREFIX)
+            .map(|prefix| u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize)
+            .ok_or_else(invalid)?;
+        let body_length = LENGTH_PREFIX + length;
+        if length < IV_LENGTH || rest.len() < body_length + MAC_LENGTH {
+            return Err(invalid());
+        }
+        let (body, tail) = rest.split_at(body_length);
+        let (mac, tail) = tail.split_at(MAC_LENGTH);
+
+        let index = u32::try_from(entries.len()).map_err(|_| invalid())?;
+        chain_mac(keys, &previous_mac, index, body)?
+            .verify_slice(mac)
+            .map_err(|_| napi::Error::from_reason(format!("Audit log chain is broken at entry {}", index)))?;
+
+        entries.push(ChainedEntry {
+            index,
+            previous_mac,
+            iv: &body[LENGTH_PREFIX..LENGTH_PREFIX + IV_LENGTH],
+            ciphertext: &body[LENGTH_PREFIX + IV_LENGTH..],
+        });
+        previous_mac.copy_from_slice(mac);
+        rest = tail;
+    }
+
+    Ok((entries, previous_mac))
+}
+
+fn entry_mac(keys: &AuditKeys, previous_mac: &[u8], index: u32, body: &[u8]) -> napi::Result<[u8; MAC_LENGTH]> {
+    let mut mac = [0u8; MAC_LENGTH];
+    mac.copy_from_slice(&chain_mac(keys, previous_mac, index, body)?.finalize().into_bytes());
+    Ok(mac)
+}
+
+// HMAC over the previous entry's HMAC, this entry's position and its bytes
+fn chain_mac(keys: &AuditKeys, previous_mac: &[u8], index: u32, body: &[u8]) -> napi::Result<Hmac<Sha256>> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&keys.mac)
+        .map_err(|_| napi::Error::from_reason("Failed to initialize HMAC"))?;
+    mac.update(previous_mac);
+    mac.update(&index.to_be_bytes());
+    mac.update(body);
+    Ok(mac)
+}
+
+// The ciphertext is additionally bound to its position in the chain
+fn entry_aad(index: u32, previous_mac: &[u8]) -> Vec<u8> {
+    [&index.to_be_bytes(), previous_mac].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> AuditKeys {
+        AuditKeys::derive(&[5u8; SIGNING_KEY_LENGTH]).unwrap()
+    }
+
+    fn log_of(events: &[&str], keys: &AuditKeys) -> Vec<u8> {
+        events
+            .iter()
+            .enumerate()
+            .fold(Vec::new(), |log, (index, event)| {
+                append_entry(&log, event, 1_700_000_000 + index as i64, keys).unwrap()
+            })
+    }
+
+    // Byte range of each entry (length prefix through HMAC) in a log
+    fn entry_ranges(log: &[u8]) -> Vec<std::ops::Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = AUDIT_MAGIC.len();
+        while start < log.len() {
+            let prefix = &log[start..start + LENGTH_PREFIX];
+            let length = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+            let end = start + LENGTH_PREFIX + length + MAC_LENGTH;
+            ranges.push(start..end);
+            start = end;
+        }
+        ranges
+    }
+
+    #[test]
+    fn entries_round_trip_in_order() {
+        let keys = keys();
+        let log = log_of(&["unlock", "copy code", "lock"], &keys);
+        let entries = read_entries(&log, &keys).unwrap();
+        let events: Vec<(&str, i64)> = entries.iter().map(|entry| (entry.event.as_str(), entry.timestamp)).collect();
+        assert_eq!(
+            events,
+            vec![("unlock", 1_700_000_000), ("copy code", 1_700_000_001), ("lock", 1_700_000_002)]
+        );
+    }
+
+    #[test]
+    fn any_flipped_byte_breaks_the_chain() {
+        let keys = keys();
+        let log = log_of(&["unlock", "copy code"], &keys);
+        for position in 0..log.len() {
+            let mut tampered = log.clone();
+            tampered[position] ^= 0x01;
+            assert!(verified_entries(&tampered, &keys).is_err(), "byte {} not covered", position);
+            assert!(append_entry(&tampered, "lock", 0, &keys).is_err());
+        }
+    }
+
+    #[test]
+    fn reordered_or_removed_entries_are_detected() {
+        let keys = keys();
+        let log = log_of(&["first", "second", "third"], &keys);
+        let ranges = entry_ranges(&log);
+        assert_eq!(ranges.len(), 3);
+
+        let swapped = [
+            AUDIT_MAGIC.as_slice(),
+            &log[ranges[1].clone()],
+            &log[ranges[0].clone()],
+            &log[ranges[2].clone()],
+        ]
+        .concat();
+        assert!(verified_entries(&swapped, &keys).is_err());
+
+        let without_middle = [AUDIT_MAGIC.as_slice(), &log[ranges[0].clone()], &log[ranges[2].clone()]].concat();
+        assert!(verified_entries(&without_middle, &keys).is_err());
+
+        // Known limit: cutting entries off the end leaves a valid, shorter log
+        let without_last = &log[..ranges[2].start];
+        assert_eq!(read_entries(without_last, &keys).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn another_key_cannot_verify_or_extend() {
+        let log = log_of(&["unlock"], &keys());
+        let other = AuditKeys::derive(&[6u8; SIGNING_KEY_LENGTH]).unwrap();
+        assert!(verified_entries(&log, &other).is_err());
+        assert!(append_entry(&log, "lock", 0, &other).is_err());
+        assert!(AuditKeys::derive(&[5u8; SIGNING_KEY_LENGTH - 1]).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+// Clock-health helpers: how much device clock error a vault can absorb
+
+use napi_derive::napi;
+
+use crate::account::Account;
+use crate::{current_timestamp, time_counter};
+
+// Largest clock error (in seconds, either direction) every time-based account
+// still tolerates when the verifier accepts +/- `window` time steps
+// A skew of `window * period` shifts codes by at most `window` steps, so the
+// account with the shortest period is the limiting factor. HOTP accounts do
+// not depend on the clock and are ignored.
+#[napi]
+pub fn max_tolerable_skew(accounts: Vec<Account>, window: u32) -> napi::Result<i32> {
+    if accounts.is_empty() {
+        return Err(napi::Error::from_reason("No accounts provided"));
+    }
+
+    let mut shortest_period: Option<u32> = None;
+    for account in &accounts {
+        if let Some(period) = account.time_step()? {
+            shortest_period = Some(shortest_period.map_or(period, |current| current.min(period)));
+        }
+    }
+
+    let period = shortest_period.ok_or_else(|| napi::Error::from_reason("No time-based accounts provided"))?;
+    i32::try_from(period as u64 * window as u64)
+        .map_err(|_| napi::Error::from_reason("Window is too large"))
+}
+
+// Number of time-based accounts whose current codes a verifier running at
+// `server_time` would reject, given this device's clock (`timestamp`, defaults
+// to the current time) and +/- `window` steps
+// Each account is checked against its own period: the same skew can move a
+// 30-second account by one step and leave a 60-second account untouched.
+#[napi]
+pub fn estimate_failing_accounts(
+    accounts: Vec<Account>,
+    server_time: i64,
+    window: u32,
+    timestamp: Option<i64>,
+) -> napi::Result<u32> {
+    let device_time = timestamp.unwrap_or_else(current_timestamp);
+
+    let mut failing = 0;
+    for account in &accounts {
+        if let Some(period) = account.time_step()? {
+            let drift = time_counter(device_time, period, 0)? - time_counter(server_time, period, 0)?;
+            if drift.unsigned_abs() > window as u64 {
+                failing += 1;
+            }
+        }
+    }
+    Ok(failing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(period: Option<u32>, otp_type: Option<&str>) -> Account {
+        Account {
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            period,
+            otp_type: otp_type.map(str::to_string),
+            counter: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn max_tolerable_skew_uses_the_shortest_period() {
+        let accounts = vec![account(Some(60), None), account(None, None), account(None, Some("hotp"))];
+        assert_eq!(max_tolerable_skew(accounts, 1).unwrap(), 30);
+        assert_eq!(max_tolerable_skew(vec![account(Some(60), None)], 2).unwrap(), 120);
+        assert!(max_tolerable_skew(vec![account(None, Some("hotp"))], 1).is_err());
+        assert!(max_tolerable_skew(Vec::new(), 1).is_err());
+    }
+
+    #[test]
+    fn skew_affects_each_period_separately() {
+        let accounts = vec![account(None, None), account(Some(60), None), account(None, Some("hotp"))];
+        let server = 1_700_000_050;
+
+        // In sync: nothing fails
+        assert_eq!(estimate_failing_accounts(accounts.clone(), server, 0, Some(server)).unwrap(), 0);
+        // 25 seconds ahead: the 30-second account moves a step, the 60-second one does not
+        assert_eq!(estimate_failing_accounts(accounts.clone(), server, 0, Some(server + 25)).unwrap(), 1);
+        // ... and a window of 1 absorbs it
+        assert_eq!(estimate_failing_accounts(accounts.clone(), server, 1, Some(server + 25)).unwrap(), 0);
+        // Two minutes behind fails both time-based accounts even with a window of 1
+        assert_eq!(estimate_failing_accounts(accounts, server, 1, Some(server - 120)).unwrap(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+// Diagnostics for encrypted blobs: a size breakdown for developers, and an
+// opt-in, anonymous signature for failed unlocks
+//
+// Nothing here ever sees a password. For the signature, no blob byte reaches
+// the output: the blob's magic and length only pick one of a few format labels,
+// and the error code must be one of the crate's own codes. The signature is a hash of those two
+// enumerated values, so it can take only a few dozen distinct values and
+// identifies a failure class, never a user or a vault.
+
+use data_encoding::HEXLOWER;
+use napi_derive::napi;
+use sha2::{Digest, Sha256};
+
+use crate::{account, plain, stamped, timelock, ErrorCode, IV_LENGTH, SALT_LENGTH, TAG_LENGTH};
+
+const SIGNATURE_DOMAIN: &str = "truefa-decrypt-failure-v1";
+const SIGNATURE_HEX_LENGTH: usize = 16;
+// Label for unencrypted vaults, which have no salt, iv or tag to speak of
+const PLAIN_FORMAT: &str = "plain";
+
+// Bucket id for a failed decryption, safe to attach to crash reports
+// `error_code` is the `errorCode` of the failed CryptoResult; unknown codes are
+// reported as "UNKNOWN" so arbitrary strings cannot be smuggled through
+#[napi]
+pub fn decrypt_failure_signature(encrypted_data: String, error_code: String) -> String {
+    let format = blob_format(&encrypted_data);
+    let category = ErrorCode::from_name(error_code.trim()).map_or("UNKNOWN", ErrorCode::as_str);
+
+    let digest = Sha256::digest(format!("{}|{}|{}", SIGNATURE_DOMAIN, format, category).as_bytes());
+    let mut signature = HEXLOWER.encode(&digest);
+    signature.truncate(SIGNATURE_HEX_LENGTH);
+    signature
+}
+
+// Coarse format label derived from the blob's shape only
+fn blob_format(encrypted_data: &str) -> &'static str {
+    match base64::decode(encrypted_data.trim()) {
+        Err(_) => "not-base64",
+        Ok(blob) => {
+            let (format, header) = detect_format(&blob);
+            if format == PLAIN_FORMAT {
+                format
+            } else if blob.len() < header + SALT_LENGTH + IV_LENGTH + TAG_LENGTH {
+                "short"
+            } else {
+                format
+            }
+        }
+    }
+}
+
+// Format label and header length of a decoded blob, recognized by its magic
+// Anything without a known magic is taken as a plain v1 blob with no header.
+// Both the failure signature and the size breakdown go through here, so every
+// new container format only needs adding once.
+fn detect_format(blob: &[u8]) -> (&'static str, usize) {
+    if blob.starts_with(plain::PLAIN_MAGIC) {
+        (PLAIN_FORMAT, plain::PLAIN_MAGIC.len())
+    } else if blob.starts_with(timelock::TIMELOCK_MAGIC) {
+        ("timelocked", timelock::HEADER_LENGTH)
+    } else if blob.starts_with(stamped::STAMPED_MAGIC) {
+        ("timestamped", stamped::HEADER_LENGTH)
+    } else if blob.starts_with(account::SINGLE_ACCOUNT_MAGIC) {
+        ("single-account", account::SINGLE_ACCOUNT_MAGIC.len())
+    } else {
+        ("v1", 0)
+    }
+}
+
+// Where the bytes of an encrypted blob go
+#[napi(object)]
+pub struct SizeBreakdown {
+    // "v1", "timelocked", "timestamped" or "single-account"
+    pub format: String,
+    // Length of the base64 text
+    pub encoded: u32,
+    // Length after base64 decoding; the sum of the components below
+    pub decoded: u32,
+    pub header: u32,
+    pub salt: u32,
+    pub iv: u32,
+    // Encrypted payload, the same length as the plaintext
+    pub ciphertext: u32,
+    pub tag: u32,
+    pub base64_overhead: u32,
+}
+
+// Byte count of each part of a blob, without decrypting it
+// The format is recognized by `detect_format`; unencrypted vaults are refused.
+// Whitespace around the text is ignored.
+#[napi]
+pub fn blob_size_breakdown(encrypted_data: String) -> napi::Result<SizeBreakdown> {
+    let encoded = encrypted_data.trim();
+    let blob = base64::decode(encoded).map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+
+    let (format, header) = detect_format(&blob);
+    if format == PLAIN_FORMAT {
+        return Err(napi::Error::from_reason("Not an encrypted blob: this is an unencrypted vault"));
+    }
+
+    let overhead = header + SALT_LENGTH + IV_LENGTH + TAG_LENGTH;
+    if blob.len() < overhead {
+        return Err(napi::Error::from_reason(format!(
+            "Blob is too short for the {} format: {} bytes, at least {} expected",
+            format,
+            blob.len(),
+            overhead
+        )));
+    }
+
+    Ok(SizeBreakdown {
+        format: format.to_string(),
+        encoded: encoded.len() as u32,
+        decoded: blob.len() as u32,
+        header: header as u32,
+        salt: SALT_LENGTH as u32,
+        iv: IV_LENGTH as u32,
+        ciphertext: (blob.len() - overhead) as u32,
+        tag: TAG_LENGTH as u32,
+        base64_overhead: (encoded.len() - blob.len()) as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Zero-filled blob starting with `magic`, with a `header`-byte header and `payload` ciphertext bytes
+    fn blob(magic: &[u8], header: usize, payload: usize) -> String {
+        let mut blob = magic.to_vec();
+        blob.resize(header + SALT_LENGTH + IV_LENGTH + payload + TAG_LENGTH, 0);
+        base64::encode(blob)
+    }
+
+    fn signature(encrypted_data: &str) -> String {
+        decrypt_failure_signature(encrypted_data.to_string(), "AUTHENTICATION_FAILED".to_string())
+    }
+
+    #[test]
+    fn signature_format_labels() {
+        assert_eq!(blob_format("not base64!"), "not-base64");
+        assert_eq!(blob_format(&base64::encode([0u8; 8])), "short");
+        assert_eq!(blob_format(&blob(&[], 0, 10)), "v1");
+        assert_eq!(blob_format(&blob(timelock::TIMELOCK_MAGIC, timelock::HEADER_LENGTH, 10)), "timelocked");
+        assert_eq!(blob_format(&blob(stamped::STAMPED_MAGIC, stamped::HEADER_LENGTH, 10)), "timestamped");
+        let single = blob(account::SINGLE_ACCOUNT_MAGIC, account::SINGLE_ACCOUNT_MAGIC.len(), 10);
+        assert_eq!(blob_format(&single), "single-account");
+    }
+
+    #[test]
+    fn signature_tells_timelocked_from_v1() {
+        let v1 = signature(&blob(&[], 0, 10));
+        let timelocked = signature(&blob(timelock::TIMELOCK_MAGIC, timelock::HEADER_LENGTH, 10));
+        assert_ne!(v1, timelocked);
+        assert_eq!(timelocked.len(), SIGNATURE_HEX_LENGTH);
+        // Only the format counts, not the length or contents
+        assert_eq!(timelocked, signature(&blob(timelock::TIMELOCK_MAGIC, timelock::HEADER_LENGTH, 500)));
+    }
+
+    #[test]
+    fn timelocked_blob_too_short_for_its_header_is_short() {
+        // Long enough for a v1 blob, not for a v1 blob behind the time-lock header
+        let truncated = blob(timelock::TIMELOCK_MAGIC, 0, 4);
+        assert_eq!(blob_format(&truncated), "short");
+        assert!(blob_size_breakdown(truncated).is_err());
+    }
+
+    #[test]
+    fn size_breakdown_of_a_timelocked_blob() {
+        let encrypted = timelock::encrypt_data_timelocked("secret".to_string(), "hunter2".to_string(), 1_800_000_000);
+        assert!(encrypted.success);
+
+        let breakdown = blob_size_breakdown(encrypted.data.clone()).unwrap();
+        assert_eq!(breakdown.format, "timelocked");
+        assert_eq!(breakdown.header, timelock::HEADER_LENGTH as u32);
+        assert_eq!(breakdown.ciphertext, "secret".len() as u32);
+        assert_eq!(
+            breakdown.decoded,
+            breakdown.header + breakdown.salt + breakdown.iv + breakdown.ciphertext + breakdown.tag
+        );
+        assert_eq!(breakdown.encoded, encrypted.data.len() as u32);
+        assert_eq!(blob_format(&encrypted.data), "timelocked");
+    }
+
+    #[test]
+    fn signature_tells_timestamped_from_the_other_formats() {
+        let timestamped = signature(&blob(stamped::STAMPED_MAGIC, stamped::HEADER_LENGTH, 10));
+        assert_ne!(timestamped, signature(&blob(&[], 0, 10)));
+        assert_ne!(timestamped, signature(&blob(timelock::TIMELOCK_MAGIC, timelock::HEADER_LENGTH, 10)));
+        // A stamped blob missing part of its header is short, even if a v1 blob would fit
+        let truncated = blob(stamped::STAMPED_MAGIC, 0, 4);
+        assert_eq!(blob_format(&truncated), "short");
+        assert!(blob_size_breakdown(truncated).is_err());
+    }
+
+    #[test]
+    fn size_breakdown_of_a_timestamped_blob() {
+        let encrypted = stamped::encrypt_data_stamped("secret".to_string(), "hunter2".to_string(), Some(1), Some(2));
+        assert!(encrypted.success);
+
+        let breakdown = blob_size_breakdown(encrypted.data.clone()).unwrap();
+        assert_eq!(breakdown.format, "timestamped");
+        assert_eq!(breakdown.header, stamped::HEADER_LENGTH as u32);
+        assert_eq!(breakdown.ciphertext, "secret".len() as u32);
+        assert_eq!(blob_format(&encrypted.data), "timestamped");
+    }
+
+    #[test]
+    fn single_account_backups_have_their_own_format() {
+        let backup = blob(account::SINGLE_ACCOUNT_MAGIC, account::SINGLE_ACCOUNT_MAGIC.len(), 10);
+        let single = signature(&backup);
+        assert_ne!(single, signature(&blob(&[], 0, 10)));
+        assert_ne!(single, signature(&blob(stamped::STAMPED_MAGIC, stamped::HEADER_LENGTH, 10)));
+        assert_eq!(blob_format(&blob(account::SINGLE_ACCOUNT_MAGIC, 0, 2)), "short");
+
+        let breakdown = blob_size_breakdown(backup).unwrap();
+        assert_eq!(breakdown.format, "single-account");
+        assert_eq!(breakdown.header, account::SINGLE_ACCOUNT_MAGIC.len() as u32);
+        assert_eq!(breakdown.ciphertext, 10);
+    }
+
+    #[test]
+    fn unencrypted_vaults_are_labeled_and_not_broken_down() {
+        let stored = base64::encode([plain::PLAIN_MAGIC, b"[]".as_slice()].concat());
+        assert_eq!(blob_format(&stored), "plain");
+        assert_ne!(signature(&stored), signature(&blob(&[], 0, 10)));
+        let error = blob_size_breakdown(stored).err().unwrap();
+        assert_eq!(error.reason, "Not an encrypted blob: this is an unencrypted vault");
+
+        // Even a bare label is recognized rather than reported as short
+        assert_eq!(blob_format(&base64::encode(plain::PLAIN_MAGIC)), "plain");
+    }
+}
@@ -0,0 +1,109 @@
+// Deterministic fixtures for frontend integration tests
+// Only compiled with the `test-fixtures` feature; never enable it for release builds.
+//
+// Synthetic secret scheme: account `i` gets the 20-byte secret
+//   HKDF-SHA256(ikm = seed as 4 big-endian bytes, salt = "", info = "truefa-fixture-secret" || i)
+// encoded as base32. The salt and iv of the vault blob are derived the same way
+// (info "truefa-fixture-salt" / "truefa-fixture-iv"), so identical inputs always
+// produce byte-identical vaults.
+// `deterministic_secret` uses the same derivation with the UTF-8 seed phrase as
+// ikm and info "truefa-fixture-phrase-secret" || i.
+
+use data_encoding::BASE32;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::account::Account;
+use crate::{encrypt_bytes_with, hkdf_sha256, SecureSecret, IV_LENGTH, SALT_LENGTH};
+
+const FIXTURE_SECRET_LENGTH: usize = 20;
+
+// Fixed epoch for synthetic creation dates (2024-01-01T00:00:00Z, in milliseconds)
+const FIXTURE_CREATED_AT: i64 = 1_704_067_200_000;
+
+const FIXTURE_ISSUERS: [&str; 5] = ["GitHub", "Google", "Microsoft", "Dropbox", "Example"];
+
+// Build a reproducible encrypted vault with `account_count` synthetic accounts
+// The returned bytes are the raw blob; `toString('base64')` gives what `decrypt_data` accepts
+#[napi]
+pub fn make_test_vault(account_count: u32, password: String, seed: u32) -> napi::Result<Buffer> {
+    test_vault(account_count, &password, seed).map(Buffer::from)
+}
+
+fn test_vault(account_count: u32, password: &str, seed: u32) -> napi::Result<Vec<u8>> {
+    let accounts = (0..account_count)
+        .map(|index| fixture_account(seed, index))
+        .collect::<napi::Result<Vec<_>>>()?;
+    let json = serde_json::to_vec(&accounts)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut iv = [0u8; IV_LENGTH];
+    hkdf_sha256(&seed.to_be_bytes(), &[], b"truefa-fixture-salt", &mut salt)?;
+    hkdf_sha256(&seed.to_be_bytes(), &[], b"truefa-fixture-iv", &mut iv)?;
+
+    encrypt_bytes_with(&json, password, &salt, &iv, &[]).map_err(napi::Error::from)
+}
+
+// Reproducible secret number `index` for a seed phrase, for test suites that
+// need stable secrets without hardcoding base32
+#[napi]
+pub fn deterministic_secret(seed: String, index: u32) -> napi::Result<SecureSecret> {
+    let info = [b"truefa-fixture-phrase-secret".as_slice(), &index.to_be_bytes()].concat();
+    let mut secret = vec![0u8; FIXTURE_SECRET_LENGTH];
+    hkdf_sha256(seed.as_bytes(), &[], &info, &mut secret)?;
+    Ok(SecureSecret::from_bytes(secret))
+}
+
+fn fixture_account(seed: u32, index: u32) -> napi::Result<Account> {
+    let info = [b"truefa-fixture-secret".as_slice(), &index.to_be_bytes()].concat();
+    let mut secret = [0u8; FIXTURE_SECRET_LENGTH];
+    hkdf_sha256(&seed.to_be_bytes(), &[], &info, &mut secret)?;
+
+    let issuer = FIXTURE_ISSUERS[index as usize % FIXTURE_ISSUERS.len()];
+    Ok(Account {
+        id: format!("fixture-{}-{}", seed, index),
+        name: format!("user{}@example.com", index),
+        issuer: issuer.to_string(),
+        secret: BASE32.encode(&secret),
+        created_at: FIXTURE_CREATED_AT + index as i64 * 1000,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vaults_are_reproducible() {
+        let vault = test_vault(3, "hunter2", 7).unwrap();
+        assert_eq!(vault, test_vault(3, "hunter2", 7).unwrap());
+        assert_ne!(vault, test_vault(3, "hunter2", 8).unwrap());
+    }
+
+    #[test]
+    fn vault_decrypts_to_the_synthetic_accounts() {
+        let vault = test_vault(6, "hunter2", 7).unwrap();
+        let json = crate::decrypt_bytes(&vault, "hunter2").unwrap();
+        let accounts: Vec<Account> = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(accounts.len(), 6);
+        assert_eq!(accounts[0].id, "fixture-7-0");
+        assert_eq!(accounts[5].issuer, "GitHub");
+        assert_eq!(accounts[1].created_at, FIXTURE_CREATED_AT + 1000);
+        assert_eq!(accounts[2], fixture_account(7, 2).unwrap());
+        assert!(accounts.iter().all(|account| SecureSecret::new(account.secret.clone()).is_ok()));
+    }
+
+    #[test]
+    fn deterministic_secrets_depend_on_seed_and_index() {
+        let secret = |seed: &str, index: u32| {
+            deterministic_secret(seed.to_string(), index).unwrap().bytes().unwrap().to_vec()
+        };
+        assert_eq!(secret("alpha", 0), secret("alpha", 0));
+        assert_eq!(secret("alpha", 0).len(), FIXTURE_SECRET_LENGTH);
+        assert_ne!(secret("alpha", 0), secret("alpha", 1));
+        assert_ne!(secret("alpha", 0), secret("beta", 0));
+    }
+}
@@ -0,0 +1,224 @@
+// Structural checks for a decrypted vault, run after import or restore
+
+use std::collections::hash_map::{Entry, HashMap};
+
+use napi_derive::napi;
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::{decode_base32, decode_secret, ResolvedParams};
+
+// One problem found in a vault
+#[napi(object)]
+pub struct IntegrityIssue {
+    // Position of the account in the input list
+    pub index: u32,
+    pub account_id: String,
+    // Account field at fault, e.g. "secret" or "digits"
+    pub field: String,
+    pub message: String,
+}
+
+// List everything wrong with a decrypted account list; empty for a clean vault
+// Covers missing required fields, duplicate ids, undecodable secrets and
+// parameters no code could be generated with
+#[napi]
+pub fn validate_vault_integrity(accounts: Vec<Account>) -> Vec<IntegrityIssue> {
+    let mut issues = Vec::new();
+    let mut first_index_by_id: HashMap<&str, usize> = HashMap::new();
+
+    for (index, account) in accounts.iter().enumerate() {
+        let mut report = |field: &str, message: String| {
+            issues.push(IntegrityIssue {
+                index: index as u32,
+                account_id: account.id.clone(),
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if account.id.trim().is_empty() {
+            report("id", "Account has no id".to_string());
+        } else {
+            match first_index_by_id.entry(account.id.as_str()) {
+                Entry::Occupied(first) => {
+                    report("id", format!("Duplicate account id (first used by account {})", first.get()))
+                }
+                Entry::Vacant(slot) => {
+                    slot.insert(index);
+                }
+            }
+        }
+
+        if account.name.trim().is_empty() && account.issuer.trim().is_empty() {
+            report("name", "Account has neither a name nor an issuer".to_string());
+        }
+
+        match decode_base32(&account.secret) {
+            Some(bytes) if bytes.is_empty() => report("secret", "Secret is empty".to_string()),
+            Some(mut bytes) => bytes.zeroize(),
+            None => report("secret", "Secret is not valid Base32".to_string()),
+        }
+
+        let otp_type = account.otp_type.as_deref().unwrap_or("totp").to_lowercase();
+        match otp_type.as_str() {
+            "totp" | "hotp" | "steam" => {
+                if let Err(error) = ResolvedParams::resolve(Some(account.params())) {
+                    report("params", error.reason);
+                }
+            }
+            other => report("otpType", format!("Unsupported OTP type: {}", other)),
+        }
+
+        if otp_type == "hotp" {
+            match account.counter {
+                None => report("counter", "HOTP account has no counter".to_string()),
+                Some(counter) if counter < 0 => report("counter", "Counter must not be negative".to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    issues
+}
+
+// Positions of accounts whose secret can no longer be loaded now that empty secrets
+// are rejected, so the app can ask the user to re-enter them after an upgrade
+// instead of those accounts failing one by one
+#[napi]
+pub fn scan_for_invalid_secrets(accounts: Vec<Account>) -> Vec<u32> {
+    accounts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, account)| match decode_secret(&account.secret) {
+            Ok(mut bytes) => {
+                bytes.zeroize();
+                None
+            }
+            Err(_) => Some(index as u32),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(secret: &str) -> Account {
+        Account {
+            secret: secret.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn valid(id: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: "alice@example.com".to_string(),
+            issuer: "Example".to_string(),
+            ..account("JBSWY3DPEHPK3PXP")
+        }
+    }
+
+    // (index, field) of every issue found
+    fn issues(accounts: Vec<Account>) -> Vec<(u32, String)> {
+        validate_vault_integrity(accounts)
+            .into_iter()
+            .map(|issue| (issue.index, issue.field))
+            .collect()
+    }
+
+    #[test]
+    fn clean_vault_has_no_issues() {
+        let hotp = Account {
+            otp_type: Some("HOTP".to_string()),
+            counter: Some(0),
+            ..valid("c")
+        };
+        assert!(validate_vault_integrity(vec![valid("a"), valid("b"), hotp]).is_empty());
+        assert!(validate_vault_integrity(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn duplicate_ids_point_at_the_first_use() {
+        let found = validate_vault_integrity(vec![valid("a"), valid("b"), valid("a"), valid("a")]);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].index, found[0].account_id.as_str(), found[0].field.as_str()), (2, "a", "id"));
+        assert_eq!(found[0].message, "Duplicate account id (first used by account 0)");
+        assert_eq!(found[1].message, "Duplicate account id (first used by account 0)");
+
+        let unnamed = Account {
+            name: String::new(),
+            issuer: " ".to_string(),
+            ..valid(" ")
+        };
+        assert_eq!(issues(vec![unnamed]), vec![(0, "id".to_string()), (0, "name".to_string())]);
+    }
+
+    #[test]
+    fn undecodable_secrets_are_reported() {
+        let secrets = ["", "====", "not base32!"];
+        let accounts = secrets
+            .iter()
+            .enumerate()
+            .map(|(index, secret)| Account {
+                secret: secret.to_string(),
+                ..valid(&index.to_string())
+            })
+            .collect();
+        let found = validate_vault_integrity(accounts);
+
+        assert!(found.iter().all(|issue| issue.field == "secret"));
+        let messages: Vec<&str> = found.iter().map(|issue| issue.message.as_str()).collect();
+        assert_eq!(messages, ["Secret is empty", "Secret is empty", "Secret is not valid Base32"]);
+    }
+
+    #[test]
+    fn unusable_parameters_are_reported() {
+        let accounts = vec![
+            Account {
+                digits: Some(3),
+                ..valid("digits")
+            },
+            Account {
+                period: Some(0),
+                ..valid("period")
+            },
+            Account {
+                otp_type: Some("motp".to_string()),
+                ..valid("type")
+            },
+            Account {
+                otp_type: Some("hotp".to_string()),
+                counter: None,
+                ..valid("no-counter")
+            },
+            Account {
+                otp_type: Some("hotp".to_string()),
+                counter: Some(-1),
+                ..valid("negative-counter")
+            },
+        ];
+        let expected = [(0, "params"), (1, "params"), (2, "otpType"), (3, "counter"), (4, "counter")];
+        let expected: Vec<(u32, String)> = expected.iter().map(|(index, field)| (*index, field.to_string())).collect();
+        assert_eq!(issues(accounts), expected);
+    }
+
+    #[test]
+    fn scan_reports_only_unloadable_secrets() {
+        let accounts = vec![
+            account("JBSWY3DPEHPK3PXP"),
+            account(""),
+            account("===="),
+            account("not base32!"),
+            account("jbsw y3dp ehpk 3pxp"),
+        ];
+        assert_eq!(scan_for_invalid_secrets(accounts), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn scan_of_a_healthy_vault_is_empty() {
+        assert!(scan_for_invalid_secrets(vec![account("JBSWY3DPEHPK3PXP"); 3]).is_empty());
+        assert!(scan_for_invalid_secrets(Vec::new()).is_empty());
+    }
+}
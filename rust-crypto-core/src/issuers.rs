@@ -0,0 +1,143 @@
+// Known parameters of services whose codes differ from the RFC 6238 defaults
+//
+// Imports from other apps and hand-typed secrets often lose the parameters a
+// service needs. Built-in table (issuer names match case-insensitively):
+//   steam, steam guard   otp type "steam" (Steam's 5-character encoding)
+//   battle.net, blizzard 8 digits
+// Entries are added only when a service's behavior is well established;
+// apps can pass their own (e.g. for regional banks) to extend it.
+
+use napi_derive::napi;
+
+use crate::account::Account;
+
+// Parameters to fill in for one issuer; unset fields are left alone
+#[napi(object)]
+pub struct IssuerDefaults {
+    pub issuer: String,
+    pub otp_type: Option<String>,
+    pub digits: Option<u32>,
+    pub period: Option<u32>,
+    pub algorithm: Option<String>,
+}
+
+// (issuer, otp type, digits, period, algorithm)
+type KnownIssuer = (
+    &'static str,
+    Option<&'static str>,
+    Option<u32>,
+    Option<u32>,
+    Option<&'static str>,
+);
+
+const KNOWN_ISSUERS: [KnownIssuer; 4] = [
+    ("steam", Some("steam"), None, None, None),
+    ("steam guard", Some("steam"), None, None, None),
+    ("battle.net", None, Some(8), None, None),
+    ("blizzard", None, Some(8), None, None),
+];
+
+// Fill in missing parameters of accounts from recognized issuers
+// Only fields the account leaves unset are filled; explicit values always win.
+// `extra` entries are checked before the built-in table, so they can also
+// replace a built-in entry. Accounts of unknown issuers are returned unchanged.
+#[napi]
+pub fn apply_known_issuer_defaults(accounts: Vec<Account>, extra: Option<Vec<IssuerDefaults>>) -> Vec<Account> {
+    let mut table = extra.unwrap_or_default();
+    table.extend(KNOWN_ISSUERS.iter().map(|&(issuer, otp_type, digits, period, algorithm)| IssuerDefaults {
+        issuer: issuer.to_string(),
+        otp_type: otp_type.map(str::to_string),
+        digits,
+        period,
+        algorithm: algorithm.map(str::to_string),
+    }));
+
+    accounts
+        .into_iter()
+        .map(|mut account| {
+            let issuer = account.issuer.trim();
+            let defaults = table
+                .iter()
+                .find(|entry| !issuer.is_empty() && entry.issuer.trim().eq_ignore_ascii_case(issuer));
+            if let Some(defaults) = defaults {
+                fill(&mut account.otp_type, &defaults.otp_type);
+                fill(&mut account.digits, &defaults.digits);
+                fill(&mut account.period, &defaults.period);
+                fill(&mut account.algorithm, &defaults.algorithm);
+            }
+            account
+        })
+        .collect()
+}
+
+fn fill<T: Clone>(field: &mut Option<T>, default: &Option<T>) {
+    if field.is_none() {
+        *field = default.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(issuer: &str) -> Account {
+        Account {
+            issuer: issuer.to_string(),
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn known_issuers_get_their_parameters() {
+        let accounts = vec![account(" Steam "), account("BATTLE.NET"), account("Example")];
+        let filled = apply_known_issuer_defaults(accounts, None);
+        assert_eq!(filled[0].otp_type.as_deref(), Some("steam"));
+        assert_eq!(filled[1].digits, Some(8));
+        assert_eq!(filled[2], account("Example"));
+    }
+
+    #[test]
+    fn explicit_values_are_never_overridden() {
+        let explicit = Account {
+            otp_type: Some("totp".to_string()),
+            digits: Some(6),
+            ..account("Blizzard")
+        };
+        let steam_with_digits = Account {
+            digits: Some(5),
+            ..account("Steam Guard")
+        };
+
+        let filled = apply_known_issuer_defaults(vec![explicit.clone(), steam_with_digits], None);
+        assert_eq!(filled[0], explicit);
+        assert_eq!((filled[1].otp_type.as_deref(), filled[1].digits), (Some("steam"), Some(5)));
+    }
+
+    #[test]
+    fn extra_entries_extend_and_replace_the_table() {
+        let extra = vec![
+            IssuerDefaults {
+                issuer: "Regional Bank".to_string(),
+                otp_type: None,
+                digits: Some(8),
+                period: Some(60),
+                algorithm: Some("SHA256".to_string()),
+            },
+            IssuerDefaults {
+                issuer: "battle.net".to_string(),
+                otp_type: None,
+                digits: Some(7),
+                period: None,
+                algorithm: None,
+            },
+        ];
+
+        let filled = apply_known_issuer_defaults(vec![account("regional bank"), account("Battle.net")], Some(extra));
+        assert_eq!((filled[0].digits, filled[0].period), (Some(8), Some(60)));
+        assert_eq!(filled[0].algorithm.as_deref(), Some("SHA256"));
+        assert_eq!(filled[1].digits, Some(7));
+        // An account without an issuer never matches
+        assert_eq!(apply_known_issuer_defaults(vec![account("")], None)[0], account(""));
+    }
+}
@@ -0,0 +1,815 @@
+// In-memory data encryption key (DEK) for encrypting many records under one key
+//
+// AES-GCM breaks completely if a (key, nonce) pair is ever reused. A persisted
+// DEK is loaded again in every app session, so any nonce state kept on the key
+// would restart each time; every record therefore gets a fresh random 96-bit
+// nonce instead, with no state shared between threads or sessions. Random
+// nonces stay within the usual 2^-32 collision bound for up to 2^32 records per
+// key (NIST SP 800-38D). Record layout: nonce (12 bytes) + ciphertext.
+//
+// Envelope records (`encrypt_record_with_key`) use caller-derived nonces
+// instead. They are sealed under a subkey derived from the DEK with HKDF, so a
+// derived nonce can never collide with a random one under the same AES key.
+
+use std::collections::{HashMap, HashSet};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use hmac::{Hmac, Mac};
+use napi::bindgen_prelude::{AsyncTask, Buffer};
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsBuffer, JsFunction, Task};
+use napi_derive::napi;
+use ring::constant_time;
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::{
+    derive_key, hkdf_sha256, CryptoError, ErrorCode, IV_LENGTH, MAX_PASSWORD_LENGTH, PBKDF2_ITERATIONS, SALT_LENGTH,
+};
+
+pub(crate) const KEY_LENGTH: usize = 32;
+
+// HKDF info for the envelope record subkey
+const RECORD_KEY_INFO: &[u8] = b"truefa-record";
+
+// A 256-bit key for encrypting records
+#[napi]
+pub struct DerivedKey {
+    key: [u8; KEY_LENGTH],
+    // Subkey for records at derived nonces, see the module comment
+    record_key: [u8; KEY_LENGTH],
+}
+
+#[napi]
+impl DerivedKey {
+    // Generate a fresh random key
+    #[napi(factory)]
+    pub fn generate() -> napi::Result<Self> {
+        let mut key = [0u8; KEY_LENGTH];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| napi::Error::from_reason("Failed to generate key"))?;
+        let derived = Self::from_key(&key);
+        key.zeroize();
+        derived
+    }
+
+    // Load an existing 32-byte key, e.g. one unwrapped from a vault
+    #[napi(factory)]
+    pub fn from_bytes(key: Buffer) -> napi::Result<Self> {
+        Self::from_key(&key)
+    }
+}
+
+impl DerivedKey {
+    pub(crate) fn from_key(key: &[u8]) -> napi::Result<Self> {
+        if key.len() != KEY_LENGTH {
+            return Err(napi::Error::from_reason(format!(
+                "Invalid key: expected {} bytes",
+                KEY_LENGTH
+            )));
+        }
+
+        let mut derived = Self {
+            key: [0u8; KEY_LENGTH],
+            record_key: [0u8; KEY_LENGTH],
+        };
+        derived.key.copy_from_slice(key);
+        hkdf_sha256(key, &[], RECORD_KEY_INFO, &mut derived.record_key)?;
+        Ok(derived)
+    }
+
+    fn random_nonce() -> napi::Result<[u8; IV_LENGTH]> {
+        let mut nonce = [0u8; IV_LENGTH];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| napi::Error::from_reason("Failed to generate nonce"))?;
+        Ok(nonce)
+    }
+
+    // Encrypt one record; safe to call from several threads at once
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> napi::Result<Vec<u8>> {
+        let nonce = Self::random_nonce()?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key));
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|e| napi::Error::from_reason(format!("Encryption error: {}", e)))?;
+
+        let mut sealed = Vec::with_capacity(IV_LENGTH + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    // Encrypt a record at a fixed nonce under the record subkey, without storing the nonce
+    fn seal_at(&self, nonce: &[u8; IV_LENGTH], plaintext: &[u8]) -> napi::Result<Vec<u8>> {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.record_key))
+            .encrypt(Nonce::from_slice(nonce), plaintext)
+            .map_err(|e| napi::Error::from_reason(format!("Encryption error: {}", e)))
+    }
+
+    fn open_at(&self, nonce: &[u8; IV_LENGTH], ciphertext: &[u8]) -> napi::Result<Vec<u8>> {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.record_key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| napi::Error::from_reason("Record failed authentication"))
+    }
+
+    pub(crate) fn open(&self, sealed: &[u8]) -> napi::Result<Vec<u8>> {
+        if sealed.len() < IV_LENGTH {
+            return Err(napi::Error::from_reason("Invalid encrypted record format"));
+        }
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key));
+        cipher
+            .decrypt(Nonce::from_slice(&sealed[..IV_LENGTH]), &sealed[IV_LENGTH..])
+            .map_err(|_| napi::Error::from_reason("Record failed authentication"))
+    }
+}
+
+impl Drop for DerivedKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.record_key.zeroize();
+    }
+}
+
+// Encrypt a record under a key, returning nonce + ciphertext
+#[napi]
+pub fn encrypt_with_key(key: &DerivedKey, data: Buffer) -> napi::Result<Buffer> {
+    key.seal(&data).map(Buffer::from)
+}
+
+// Decrypt a record produced by `encrypt_with_key` with the same key
+#[napi]
+pub fn decrypt_with_key(key: &DerivedKey, sealed: Buffer) -> napi::Result<Buffer> {
+    key.open(&sealed).map(Buffer::from)
+}
+
+// Below this many accounts per worker, spawning threads costs more than it saves
+const PARALLEL_MIN_CHUNK: usize = 16;
+
+// Encrypt each account as its own record (nonce + ciphertext of its JSON), in input order
+// Records are split across one worker per CPU core; every record draws its own
+// random nonce, so workers share no state. PBKDF2 is not involved, so this is
+// bound by AES-GCM and JSON encoding and scales close to linearly with cores
+// on large vaults. Small vaults are encrypted on the calling thread.
+#[napi]
+pub fn encrypt_accounts_parallel(accounts: Vec<Account>, dek: &DerivedKey) -> napi::Result<Vec<Buffer>> {
+    let records = seal_accounts_parallel(&accounts, dek)?;
+    Ok(records.into_iter().map(Buffer::from).collect())
+}
+
+fn seal_accounts_parallel(accounts: &[Account], dek: &DerivedKey) -> napi::Result<Vec<Vec<u8>>> {
+    let workers = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let chunk_size = accounts.len().div_ceil(workers).max(PARALLEL_MIN_CHUNK);
+
+    if accounts.len() <= chunk_size {
+        seal_accounts(dek, accounts)
+    } else {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = accounts
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || seal_accounts(dek, chunk)))
+                .collect();
+
+            let mut records = Vec::with_capacity(accounts.len());
+            for handle in handles {
+                let chunk = handle
+                    .join()
+                    .map_err(|_| napi::Error::from_reason("Encryption worker panicked"))??;
+                records.extend(chunk);
+            }
+            Ok(records)
+        })
+    }
+}
+
+// Account records after an incremental save
+#[napi(object)]
+pub struct SaveReport {
+    // One record per account, in the order of the accounts passed in
+    pub records: Vec<Buffer>,
+    // Account id of each record; the index to store with them
+    pub ids: Vec<String>,
+    // How many records were encrypted afresh
+    pub reencrypted: u32,
+}
+
+// Re-encrypt only the accounts in `changed_ids`, reusing the stored records of
+// all others byte for byte
+// `previous_records` and `previous_ids` are the records and index from the last
+// save (as produced by `encrypt_accounts_parallel` or this function). Accounts
+// without a previous record are encrypted as well; previous records of removed
+// accounts are dropped. Nothing is written here: persist `records` and `ids`
+// together, replacing the old pair in one step, so the index never points at
+// records from a different save.
+#[napi]
+pub fn save_changed_accounts(
+    accounts: Vec<Account>,
+    previous_records: Vec<Buffer>,
+    previous_ids: Vec<String>,
+    dek: &DerivedKey,
+    changed_ids: Vec<String>,
+) -> napi::Result<SaveReport> {
+    let (records, ids, reencrypted) = save_changed(&accounts, previous_records, previous_ids, dek, changed_ids)?;
+    Ok(SaveReport {
+        records,
+        ids,
+        reencrypted,
+    })
+}
+
+// Generic over the record type so stored records are moved into the result, never copied
+fn save_changed<R: From<Vec<u8>>>(
+    accounts: &[Account],
+    previous_records: Vec<R>,
+    previous_ids: Vec<String>,
+    dek: &DerivedKey,
+    changed_ids: Vec<String>,
+) -> napi::Result<(Vec<R>, Vec<String>, u32)> {
+    if previous_records.len() != previous_ids.len() {
+        return Err(napi::Error::from_reason(
+            "Record index does not match the records: expected one id per record",
+        ));
+    }
+    let mut previous: HashMap<String, R> = previous_ids.into_iter().zip(previous_records).collect();
+    let changed: HashSet<String> = changed_ids.into_iter().collect();
+
+    let mut records = Vec::with_capacity(accounts.len());
+    let mut ids = Vec::with_capacity(accounts.len());
+    let mut reencrypted = 0;
+    let mut seen = HashSet::with_capacity(accounts.len());
+    for account in accounts {
+        if !seen.insert(account.id.as_str()) {
+            return Err(napi::Error::from_reason(format!("Duplicate account id: {}", account.id)));
+        }
+        let stored = previous.remove(&account.id).filter(|_| !changed.contains(&account.id));
+        let record = match stored {
+            Some(record) => record,
+            None => {
+                reencrypted += 1;
+                R::from(seal_account(dek, account)?)
+            }
+        };
+        records.push(record);
+        ids.push(account.id.clone());
+    }
+    Ok((records, ids, reencrypted))
+}
+
+fn seal_accounts(dek: &DerivedKey, accounts: &[Account]) -> napi::Result<Vec<Vec<u8>>> {
+    accounts.iter().map(|account| seal_account(dek, account)).collect()
+}
+
+fn seal_account(dek: &DerivedKey, account: &Account) -> napi::Result<Vec<u8>> {
+    let mut json = serde_json::to_vec(account)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+    let sealed = dek.seal(&json);
+    json.zeroize();
+    sealed
+}
+
+// Nonce for record `record_index` of an envelope: the base nonce with the index
+// XORed into its last 8 bytes (big-endian)
+// Distinct indices always give distinct nonces, but only under one base nonce:
+// the base nonce must be random and never reused with the same key, e.g. by
+// generating a new one whenever the envelope is rewritten under its DEK
+#[napi]
+pub fn derive_record_nonce(base_nonce: Buffer, record_index: i64) -> napi::Result<Buffer> {
+    record_nonce(&base_nonce, record_index).map(|nonce| Buffer::from(nonce.to_vec()))
+}
+
+// Encrypt record `record_index` under a nonce derived from `base_nonce`
+// Only the ciphertext is returned; the nonce is recomputed when reading
+#[napi]
+pub fn encrypt_record_with_key(
+    key: &DerivedKey,
+    base_nonce: Buffer,
+    record_index: i64,
+    data: Buffer,
+) -> napi::Result<Buffer> {
+    let nonce = record_nonce(&base_nonce, record_index)?;
+    key.seal_at(&nonce, &data).map(Buffer::from)
+}
+
+// Decrypt a record produced by `encrypt_record_with_key`
+#[napi]
+pub fn decrypt_record_with_key(
+    key: &DerivedKey,
+    base_nonce: Buffer,
+    record_index: i64,
+    ciphertext: Buffer,
+) -> napi::Result<Buffer> {
+    let nonce = record_nonce(&base_nonce, record_index)?;
+    key.open_at(&nonce, &ciphertext).map(Buffer::from)
+}
+
+fn record_nonce(base_nonce: &[u8], record_index: i64) -> napi::Result<[u8; IV_LENGTH]> {
+    if base_nonce.len() != IV_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid base nonce: expected {} bytes",
+            IV_LENGTH
+        )));
+    }
+    let index = u64::try_from(record_index)
+        .map_err(|_| napi::Error::from_reason("Invalid record index: must not be negative"))?;
+
+    let mut nonce = [0u8; IV_LENGTH];
+    nonce.copy_from_slice(base_nonce);
+    for (byte, index_byte) in nonce[IV_LENGTH - 8..].iter_mut().zip(index.to_be_bytes()) {
+        *byte ^= index_byte;
+    }
+    Ok(nonce)
+}
+
+// A key-encryption key split into two halves kept in different places
+// Both halves are needed to rebuild the key: either alone is uniformly random
+#[napi(object)]
+pub struct KekHalves {
+    // Store alongside the vault file
+    pub file_half: Buffer,
+    // Store in the OS keychain
+    pub keychain_half: Buffer,
+}
+
+// Derive the password KEK and XOR-split it for storage in two locations
+// Losing either half makes the vault unrecoverable, so the app must warn the
+// user before enabling this and offer a recovery export
+#[napi]
+pub fn split_kek_for_storage(password: String, salt: Buffer) -> napi::Result<KekHalves> {
+    let (file_half, keychain_half) = split_kek(&password, &salt)?;
+    Ok(KekHalves {
+        file_half: Buffer::from(file_half),
+        keychain_half: Buffer::from(keychain_half),
+    })
+}
+
+// Rebuild a KEK from its two halves
+// Both input buffers are wiped in place once the key has been recombined
+#[napi]
+pub fn combine_kek_halves(mut file_half: Buffer, mut keychain_half: Buffer) -> napi::Result<DerivedKey> {
+    let key = combine_kek(&file_half, &keychain_half);
+    file_half.zeroize();
+    keychain_half.zeroize();
+    key
+}
+
+// (file half, keychain half) of the password KEK
+fn split_kek(password: &str, salt: &[u8]) -> napi::Result<(Vec<u8>, Vec<u8>)> {
+    if salt.len() != SALT_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid salt: expected {} bytes",
+            SALT_LENGTH
+        )));
+    }
+
+    let mut keychain_half = vec![0u8; KEY_LENGTH];
+    SystemRandom::new()
+        .fill(&mut keychain_half)
+        .map_err(|_| napi::Error::from_reason("Failed to generate key share"))?;
+
+    let mut kek = derive_key(password, salt)?;
+    let file_half: Vec<u8> = kek.iter().zip(&keychain_half).map(|(k, r)| k ^ r).collect();
+    kek.zeroize();
+
+    Ok((file_half, keychain_half))
+}
+
+fn combine_kek(file_half: &[u8], keychain_half: &[u8]) -> napi::Result<DerivedKey> {
+    if file_half.len() != KEY_LENGTH || keychain_half.len() != KEY_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid key share: expected {} bytes",
+            KEY_LENGTH
+        )));
+    }
+
+    let mut kek = [0u8; KEY_LENGTH];
+    for (byte, (a, b)) in kek.iter_mut().zip(file_half.iter().zip(keychain_half)) {
+        *byte = a ^ b;
+    }
+
+    let key = DerivedKey::from_key(&kek);
+    kek.zeroize();
+    key
+}
+
+// Wrap a DEK with a key held outside this crate, such as in an HSM or KMS
+// `wrap_fn(dek: Buffer) => Buffer` receives the raw key and must return it
+// wrapped, e.g. by sending it to the HSM under the handle of its wrapping key.
+// It runs synchronously on the calling JS thread and must not keep the buffer
+// it is given: that buffer is wiped as soon as it returns. Store the result in
+// place of the DEK; `unwrap_dek_with_external` reverses it.
+#[napi]
+pub fn wrap_dek_with_external(env: Env, dek: &DerivedKey, wrap_fn: JsFunction) -> napi::Result<Buffer> {
+    wrap_dek(dek, |key| call_with_key_material(env, &wrap_fn, key)).map(Buffer::from)
+}
+
+// Load a DEK wrapped by `wrap_dek_with_external`
+// `unwrap_fn(wrapped: Buffer) => Buffer` must return the raw 32-byte key; the
+// buffer it returns is wiped once the key has been loaded.
+#[napi]
+pub fn unwrap_dek_with_external(env: Env, wrapped: Buffer, unwrap_fn: JsFunction) -> napi::Result<DerivedKey> {
+    unwrap_dek(&wrapped, |wrapped| call_with_key_material(env, &unwrap_fn, wrapped))
+}
+
+// Wrap `dek` with `wrap`, refusing output that is empty or the raw key itself
+fn wrap_dek(dek: &DerivedKey, wrap: impl FnOnce(&[u8]) -> napi::Result<Vec<u8>>) -> napi::Result<Vec<u8>> {
+    let wrapped = wrap(&dek.key)?;
+    if wrapped.is_empty() || constant_time::verify_slices_are_equal(&wrapped, &dek.key).is_ok() {
+        return Err(napi::Error::from_reason("Wrap callback did not return a wrapped key"));
+    }
+    Ok(wrapped)
+}
+
+// Load the key `unwrap` recovers from `wrapped`, wiping its output afterwards
+fn unwrap_dek(wrapped: &[u8], unwrap: impl FnOnce(&[u8]) -> napi::Result<Vec<u8>>) -> napi::Result<DerivedKey> {
+    let mut key = unwrap(wrapped)?;
+    let derived = DerivedKey::from_key(&key);
+    key.zeroize();
+    derived
+}
+
+// Call `callback` with a copy of `input`, returning a copy of the Buffer it
+// returns; both JS buffers are wiped before returning
+fn call_with_key_material(env: Env, callback: &JsFunction, input: &[u8]) -> napi::Result<Vec<u8>> {
+    let argument = env.create_buffer_copy(input)?.into_raw();
+    let result = callback.call(None, &[&argument]);
+    argument.into_value()?.zeroize();
+
+    let result = result?;
+    if !result.is_buffer()? {
+        return Err(napi::Error::from_reason("Key callback must return a Buffer"));
+    }
+    let mut returned = JsBuffer::try_from(result)?.into_value()?;
+    let output = returned.to_vec();
+    returned.zeroize();
+    Ok(output)
+}
+
+// Number of progress reports over a derivation, besides the final 1.0
+const PROGRESS_STEPS: u32 = 100;
+
+// Derive a key with PBKDF2-HMAC-SHA256 like the vault does, reporting
+// progress to `progress_cb(fraction)` with values from 0.0 to 1.0 along the way
+// `iterations` defaults to the vault's count, giving the same key as vault
+// encryption. Derivation runs on the libuv thread pool and the returned promise
+// resolves with the key; progress is queued onto the JS thread between chunks
+// of iterations, so the UI can repaint while it runs.
+#[napi(ts_return_type = "Promise<DerivedKey>")]
+pub fn derive_key_with_progress(
+    password: String,
+    salt: Buffer,
+    iterations: Option<u32>,
+    progress_cb: JsFunction,
+) -> napi::Result<AsyncTask<ProgressDerivation>> {
+    let mut derivation = ProgressDerivation {
+        password,
+        salt: salt.to_vec(),
+        iterations: iterations.unwrap_or(PBKDF2_ITERATIONS),
+        progress: None,
+    };
+    if derivation.salt.len() != SALT_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid salt: expected {} bytes",
+            SALT_LENGTH
+        )));
+    }
+
+    derivation.progress = Some(progress_cb.create_threadsafe_function(
+        0,
+        |context: ThreadSafeCallContext<f64>| Ok(vec![context.env.create_double(context.value)?]),
+    )?);
+    Ok(AsyncTask::new(derivation))
+}
+
+// Background task behind `derive_key_with_progress`
+pub struct ProgressDerivation {
+    password: String,
+    salt: Vec<u8>,
+    iterations: u32,
+    progress: Option<ThreadsafeFunction<f64, ErrorStrategy::Fatal>>,
+}
+
+impl Task for ProgressDerivation {
+    type Output = [u8; KEY_LENGTH];
+    type JsValue = DerivedKey;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        let progress = self.progress.as_ref();
+        let result = pbkdf2_with_progress(&self.password, &self.salt, self.iterations, |fraction| {
+            if let Some(progress) = progress {
+                progress.call(fraction, ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        });
+        self.password.zeroize();
+        result
+    }
+
+    fn resolve(&mut self, _env: Env, mut output: Self::Output) -> napi::Result<Self::JsValue> {
+        let derived = DerivedKey::from_key(&output);
+        output.zeroize();
+        derived
+    }
+}
+
+impl Drop for ProgressDerivation {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+// PBKDF2-HMAC-SHA256 for a single 32-byte block, computed by hand so the
+// iterations can be split into chunks with a progress report between them
+fn pbkdf2_with_progress(
+    password: &str,
+    salt: &[u8],
+    iterations: u32,
+    mut on_progress: impl FnMut(f64),
+) -> napi::Result<[u8; KEY_LENGTH]> {
+    if password.len() > MAX_PASSWORD_LENGTH {
+        return Err(CryptoError::new(
+            ErrorCode::PasswordTooLong,
+            format!("Password is too long: at most {} bytes are supported", MAX_PASSWORD_LENGTH),
+        )
+        .into());
+    }
+    if iterations == 0 {
+        return Err(napi::Error::from_reason("Invalid iterations: must be greater than zero"));
+    }
+
+    let prf = <Hmac<Sha256> as Mac>::new_from_slice(password.as_bytes())
+        .map_err(|_| napi::Error::from_reason("Failed to initialize HMAC"))?;
+    let chunk = (iterations / PROGRESS_STEPS).max(1);
+
+    let mut block = [0u8; KEY_LENGTH];
+    let mut key = [0u8; KEY_LENGTH];
+    for iteration in 0..iterations {
+        if iteration % chunk == 0 {
+            on_progress(iteration as f64 / iterations as f64);
+        }
+
+        let mut mac = prf.clone();
+        if iteration == 0 {
+            // U1 = PRF(password, salt || INT(1))
+            mac.update(salt);
+            mac.update(&1u32.to_be_bytes());
+        } else {
+            mac.update(&block);
+        }
+        block.copy_from_slice(&mac.finalize().into_bytes());
+        for (byte, u) in key.iter_mut().zip(&block) {
+            *byte ^= u;
+        }
+    }
+    block.zeroize();
+
+    on_progress(1.0);
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts(count: usize) -> Vec<Account> {
+        (0..count)
+            .map(|index| Account {
+                id: format!("account-{}", index),
+                name: format!("user{}@example.com", index),
+                issuer: "Example".to_string(),
+                secret: "JBSWY3DPEHPK3PXP".to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_records_have_distinct_nonces() {
+        let dek = DerivedKey::generate().unwrap();
+        let records = seal_accounts_parallel(&accounts(1000), &dek).unwrap();
+
+        let mut nonces: Vec<&[u8]> = records.iter().map(|record| &record[..IV_LENGTH]).collect();
+        nonces.sort_unstable();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 1000);
+    }
+
+    #[test]
+    fn parallel_records_decrypt_in_order() {
+        let dek = DerivedKey::generate().unwrap();
+        for count in [0, 1, 1000] {
+            let originals = accounts(count);
+            let records = seal_accounts_parallel(&originals, &dek).unwrap();
+            assert_eq!(records.len(), count);
+
+            let opened: Vec<Account> = records
+                .iter()
+                .map(|record| serde_json::from_slice(&dek.open(record).unwrap()).unwrap())
+                .collect();
+            assert_eq!(opened, originals);
+        }
+    }
+
+    #[test]
+    fn reloaded_key_draws_fresh_nonces() {
+        let key = [3u8; KEY_LENGTH];
+        let first = DerivedKey::from_key(&key).unwrap().seal(b"record").unwrap();
+        let second = DerivedKey::from_key(&key).unwrap().seal(b"record").unwrap();
+        assert_ne!(first[..IV_LENGTH], second[..IV_LENGTH]);
+    }
+
+    #[test]
+    fn progress_derivation_rejects_oversized_password() {
+        let salt = [7u8; SALT_LENGTH];
+        let error = pbkdf2_with_progress(&"a".repeat(MAX_PASSWORD_LENGTH + 1), &salt, 1, |_| {}).unwrap_err();
+        assert!(error.reason.starts_with("Password is too long"), "{}", error.reason);
+        assert!(pbkdf2_with_progress(&"a".repeat(MAX_PASSWORD_LENGTH), &salt, 1, |_| {}).is_ok());
+    }
+
+    #[test]
+    fn progress_derivation_matches_derive_key() {
+        let salt = [7u8; SALT_LENGTH];
+        let key = pbkdf2_with_progress("correct horse", &salt, PBKDF2_ITERATIONS, |_| {}).unwrap();
+        assert_eq!(key, derive_key("correct horse", &salt).unwrap());
+    }
+
+    #[test]
+    fn progress_derivation_matches_ring_pbkdf2() {
+        let salt = [7u8; SALT_LENGTH];
+        // A password longer than the HMAC block is hashed into the key first
+        for password in ["", "hunter2", &"p".repeat(100)] {
+            for iterations in [1, 2, 1000] {
+                let mut expected = [0u8; KEY_LENGTH];
+                ring::pbkdf2::derive(
+                    ring::pbkdf2::PBKDF2_HMAC_SHA256,
+                    std::num::NonZeroU32::new(iterations).unwrap(),
+                    &salt,
+                    password.as_bytes(),
+                    &mut expected,
+                );
+                assert_eq!(pbkdf2_with_progress(password, &salt, iterations, |_| {}).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn progress_rises_to_one() {
+        let mut reports = Vec::new();
+        pbkdf2_with_progress("hunter2", &[7u8; SALT_LENGTH], 1000, |fraction| reports.push(fraction)).unwrap();
+        assert_eq!(reports.len() as u32, PROGRESS_STEPS + 1);
+        assert_eq!((reports[0], *reports.last().unwrap()), (0.0, 1.0));
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(pbkdf2_with_progress("hunter2", &[7u8; SALT_LENGTH], 0, |_| {}).is_err());
+    }
+
+    #[test]
+    fn kek_halves_recombine_to_the_password_key() {
+        let salt = [7u8; SALT_LENGTH];
+        let kek = derive_key("hunter2", &salt).unwrap();
+        let (file_half, keychain_half) = split_kek("hunter2", &salt).unwrap();
+
+        assert_ne!(file_half.as_slice(), kek.as_slice());
+        assert_ne!(keychain_half.as_slice(), kek.as_slice());
+        let record = DerivedKey::from_key(&kek).unwrap().seal(b"vault key").unwrap();
+        assert_eq!(combine_kek(&file_half, &keychain_half).unwrap().open(&record).unwrap(), b"vault key");
+
+        // The keychain half is fresh on every split
+        let (_, other_keychain_half) = split_kek("hunter2", &salt).unwrap();
+        assert_ne!(keychain_half, other_keychain_half);
+        assert!(combine_kek(&file_half, &other_keychain_half).unwrap().open(&record).is_err());
+    }
+
+    #[test]
+    fn kek_halves_reject_bad_lengths() {
+        assert!(split_kek("hunter2", &[7u8; SALT_LENGTH - 1]).is_err());
+        assert!(combine_kek(&[0u8; KEY_LENGTH], &[0u8; KEY_LENGTH - 1]).is_err());
+        assert!(combine_kek(&[0u8; KEY_LENGTH + 1], &[0u8; KEY_LENGTH]).is_err());
+        assert!(combine_kek(&[], &[]).is_err());
+    }
+
+    // Stand-in for an HSM: XOR with a fixed wrapping key
+    fn xor_wrap(key: &[u8]) -> napi::Result<Vec<u8>> {
+        Ok(key.iter().map(|byte| byte ^ 0x5c).collect())
+    }
+
+    #[test]
+    fn external_wrap_round_trip() {
+        let dek = DerivedKey::from_key(&[3u8; KEY_LENGTH]).unwrap();
+        let record = dek.seal(b"account").unwrap();
+
+        let wrapped = wrap_dek(&dek, xor_wrap).unwrap();
+        assert_ne!(wrapped.as_slice(), [3u8; KEY_LENGTH].as_slice());
+        let unwrapped = unwrap_dek(&wrapped, xor_wrap).unwrap();
+        assert_eq!(unwrapped.open(&record).unwrap(), b"account");
+    }
+
+    #[test]
+    fn external_wrap_rejects_unwrapped_output() {
+        let dek = DerivedKey::generate().unwrap();
+        assert!(wrap_dek(&dek, |_| Ok(Vec::new())).is_err());
+        assert!(wrap_dek(&dek, |key| Ok(key.to_vec())).is_err());
+        assert!(wrap_dek(&dek, |_| Err(napi::Error::from_reason("HSM offline"))).is_err());
+
+        // Unwrapping has to give back a full key
+        assert!(unwrap_dek(&[1u8; 40], |_| Ok(vec![0u8; KEY_LENGTH - 1])).is_err());
+        assert!(unwrap_dek(&[1u8; 40], |_| Ok(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn record_nonces_are_distinct_and_reproducible() {
+        let base = [0xA5u8; IV_LENGTH];
+        let mut nonces: Vec<[u8; IV_LENGTH]> = (0..1000).map(|index| record_nonce(&base, index).unwrap()).collect();
+        assert_eq!(record_nonce(&base, 0).unwrap(), base);
+        assert_eq!(nonces[417], record_nonce(&base, 417).unwrap());
+
+        nonces.sort_unstable();
+        nonces.dedup();
+        assert_eq!(nonces.len(), 1000);
+        // The upper 4 bytes never change
+        assert!(nonces.iter().all(|nonce| nonce[..4] == base[..4]));
+    }
+
+    #[test]
+    fn record_nonce_rejects_bad_input() {
+        assert!(record_nonce(&[0u8; IV_LENGTH - 1], 0).is_err());
+        assert!(record_nonce(&[0u8; IV_LENGTH], -1).is_err());
+    }
+
+    #[test]
+    fn envelope_records_use_their_own_subkey() {
+        let key = [9u8; KEY_LENGTH];
+        let dek = DerivedKey::from_key(&key).unwrap();
+        let nonce = record_nonce(&[1u8; IV_LENGTH], 3).unwrap();
+        let sealed = dek.seal_at(&nonce, b"record").unwrap();
+
+        // Not what the DEK itself would produce at that nonce
+        let direct = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .encrypt(Nonce::from_slice(&nonce), &b"record"[..])
+            .unwrap();
+        assert_ne!(sealed, direct);
+
+        assert_eq!(dek.open_at(&nonce, &sealed).unwrap(), b"record");
+        assert!(dek.open_at(&record_nonce(&[1u8; IV_LENGTH], 4).unwrap(), &sealed).is_err());
+        // A random-nonce record never opens as an envelope record, even at its own nonce
+        let random = dek.seal(b"record").unwrap();
+        let mut random_nonce = [0u8; IV_LENGTH];
+        random_nonce.copy_from_slice(&random[..IV_LENGTH]);
+        assert!(dek.open_at(&random_nonce, &random[IV_LENGTH..]).is_err());
+    }
+
+    #[test]
+    fn unchanged_records_are_reused_byte_for_byte() {
+        let dek = DerivedKey::generate().unwrap();
+        let mut current = accounts(4);
+        let ids: Vec<String> = current.iter().map(|account| account.id.clone()).collect();
+        let stored = seal_accounts_parallel(&current, &dek).unwrap();
+
+        current[2].name = "renamed@example.com".to_string();
+        let (records, saved_ids, reencrypted) =
+            save_changed(&current, stored.clone(), ids.clone(), &dek, vec![ids[2].clone()]).unwrap();
+
+        assert_eq!(reencrypted, 1);
+        assert_eq!(saved_ids, ids);
+        for index in [0, 1, 3] {
+            assert_eq!(records[index], stored[index]);
+        }
+        assert_ne!(records[2], stored[2]);
+        let reopened: Account = serde_json::from_slice(&dek.open(&records[2]).unwrap()).unwrap();
+        assert_eq!(reopened, current[2]);
+    }
+
+    #[test]
+    fn new_and_removed_accounts_are_handled() {
+        let dek = DerivedKey::generate().unwrap();
+        let before = accounts(3);
+        let ids: Vec<String> = before.iter().map(|account| account.id.clone()).collect();
+        let stored = seal_accounts_parallel(&before, &dek).unwrap();
+
+        // account-1 removed, a new account added
+        let mut after = vec![before[2].clone(), before[0].clone()];
+        after.push(Account {
+            id: "new".to_string(),
+            ..before[0].clone()
+        });
+        let (records, saved_ids, reencrypted) = save_changed(&after, stored.clone(), ids, &dek, Vec::new()).unwrap();
+        assert_eq!(saved_ids, vec!["account-2", "account-0", "new"]);
+        assert_eq!(reencrypted, 1);
+        assert_eq!((&records[0], &records[1]), (&stored[2], &stored[0]));
+    }
+
+    #[test]
+    fn save_rejects_inconsistent_input() {
+        let dek = DerivedKey::generate().unwrap();
+        let stored = seal_accounts_parallel(&accounts(2), &dek).unwrap();
+        let mismatched = save_changed(&accounts(2), stored, vec!["account-0".to_string()], &dek, Vec::new());
+        assert!(mismatched.is_err());
+
+        let duplicated = vec![accounts(1)[0].clone(), accounts(1)[0].clone()];
+        assert!(save_changed::<Vec<u8>>(&duplicated, Vec::new(), Vec::new(), &dek, Vec::new()).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 #![deny(clippy::all)]
 
+use napi::bindgen_prelude::Buffer;
 use napi_derive::napi;
 use zeroize::Zeroize;
 
@@ -8,11 +9,35 @@ use aes_gcm::{
     aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Key, Nonce,
 };
-use data_encoding::BASE32;
+use data_encoding::{BASE32_NOPAD, HEXLOWER_PERMISSIVE};
 use hmac::{Hmac, Mac};
+use ring::constant_time;
+use ring::hkdf;
 use ring::pbkdf2;
 use ring::rand::SecureRandom;
 use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+pub mod account;
+pub mod audit;
+pub mod clock;
+pub mod diagnostics;
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+pub mod integrity;
+pub mod issuers;
+pub mod keys;
+pub mod ocra;
+pub mod otpauth;
+pub mod plain;
+pub mod qr;
+pub mod ratchet;
+pub mod security;
+pub mod shamir;
+pub mod stamped;
+pub mod sync;
+pub mod timelock;
+pub mod transfer;
 
 // TOTP Constants
 const TOTP_DIGITS: usize = 6;
@@ -21,16 +46,64 @@ const TOTP_PERIOD: u64 = 30;
 // Crypto Constants
 const SALT_LENGTH: usize = 16;
 const IV_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16; // AES-GCM authentication tag
 const PBKDF2_ITERATIONS: u32 = 210000; // As mentioned in README
 
+// Longest accepted password in bytes; far beyond any passphrase, but stops
+// megabytes of pasted junk from being fed through 210k PBKDF2 rounds
+const MAX_PASSWORD_LENGTH: usize = 4096;
+
+// Supported code lengths (RFC 4226 allows 6 to 8 digits)
+const MIN_DIGITS: u32 = 6;
+const MAX_DIGITS: u32 = 8;
+
 // Type aliases for cleaner code
 type HmacSha1 = Hmac<Sha1>;
 
+// HMAC hash functions allowed by the otpauth Key URI format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    // Parse an algorithm name, accepting both "SHA1" and "SHA-1" spellings
+    fn from_name(name: &str) -> napi::Result<Self> {
+        match name.trim().to_uppercase().replace('-', "").as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(napi::Error::from_reason(format!("Unsupported algorithm: {}", name))),
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+    
+    // Size in bytes of the HMAC output
+    fn digest_len(self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+            Self::Sha512 => 64,
+        }
+    }
+}
+
 // Helper struct for securely handling secret keys
 // Will automatically zero memory when dropped
 #[napi]
 pub struct SecureSecret {
     inner: Vec<u8>,
+    // Set by `clear()`; other JS references may still hold the object afterwards
+    cleared: bool,
 }
 
 #[napi]
@@ -38,20 +111,59 @@ impl SecureSecret {
     // Create from a base32 encoded string
     #[napi(constructor)]
     pub fn new(base32_secret: String) -> napi::Result<Self> {
-        // Clean the input (remove spaces, uppercase)
-        let cleaned = base32_secret.replace(' ', "").to_uppercase();
-        
-        // Decode base32
-        match BASE32.decode(cleaned.as_bytes()) {
-            Ok(bytes) => Ok(Self { inner: bytes }),
-            Err(_) => Err(napi::Error::from_reason("Invalid Base32 encoding")),
-        }
+        decode_secret(&base32_secret).map(Self::from_bytes)
+    }
+    
+    // Create from a hex string, e.g. from exports that store raw keys as hex
+    // Decode artifacts and whitespace are removed exactly as for base32 input
+    #[napi(factory)]
+    pub fn from_hex(hex_secret: String) -> napi::Result<Self> {
+        let cleaned: String = strip_decode_artifacts(&hex_secret).chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(cleaned.as_bytes())
+            .map_err(|_| napi::Error::from_reason("Invalid hex encoding"))?;
+        Self::from_decoded(bytes)
+    }
+    
+    // Create from standard base64 (padding optional)
+    #[napi(factory)]
+    pub fn from_base64(base64_secret: String) -> napi::Result<Self> {
+        let cleaned: String = strip_decode_artifacts(&base64_secret).chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = base64::decode(cleaned.trim_end_matches('='))
+            .map_err(|_| napi::Error::from_reason("Invalid base64 encoding"))?;
+        Self::from_decoded(bytes)
     }
     
     // Explicit clear method that can be called from JavaScript
     #[napi]
     pub fn clear(&mut self) {
         self.inner.zeroize();
+        self.cleared = true;
+    }
+}
+
+impl SecureSecret {
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            inner: bytes,
+            cleared: false,
+        }
+    }
+    
+    // Decoded hex or base64 input; an empty key is rejected like in `decode_secret`
+    fn from_decoded(bytes: Vec<u8>) -> napi::Result<Self> {
+        if bytes.is_empty() {
+            return Err(napi::Error::from_reason("Secret is empty"));
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+    
+    // Key bytes, refusing to hand out a wiped secret rather than computing codes from it
+    fn bytes(&self) -> napi::Result<&[u8]> {
+        if self.cleared {
+            return Err(CryptoError::new(ErrorCode::SecretCleared, "Secret has been cleared").into());
+        }
+        Ok(&self.inner)
     }
 }
 
@@ -62,44 +174,573 @@ impl Drop for SecureSecret {
     }
 }
 
+// Invisible characters QR decoders are known to emit: the UTF-8 BOM (U+FEFF,
+// usually leading) and the zero-width space, non-joiner, joiner and word joiner
+const QR_ARTIFACTS: [char; 5] = ['\u{feff}', '\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}'];
+
+// Remove QR decode artifacts: the characters above anywhere, plus trailing
+// control bytes (NUL, CR, LF and the like). Other noise is left in place, so
+// it still fails validation rather than being silently dropped.
+pub(crate) fn strip_decode_artifacts(input: &str) -> String {
+    let cleaned: String = input.chars().filter(|c| !QR_ARTIFACTS.contains(c)).collect();
+    cleaned.trim_end_matches(char::is_control).to_string()
+}
+
+// Canonical form of user-supplied base32: decode artifacts and spaces removed, uppercased
+// Every path that accepts a base32 secret must go through this, so that
+// "jbswy3dpehpk3pxp" and "JBSWY3DPEHPK3PXP" always yield identical codes
+fn normalize_base32(secret: &str) -> String {
+    strip_decode_artifacts(secret).replace(' ', "").to_uppercase()
+}
+
+// Decode a user-supplied base32 secret
+// Padding is optional: otpauth URIs conventionally omit it, e.g. for 26-character keys
+fn decode_base32(secret: &str) -> Option<Vec<u8>> {
+    let cleaned = normalize_base32(secret);
+    BASE32_NOPAD.decode(cleaned.trim_end_matches('=').as_bytes()).ok()
+}
+
+// Decode a secret that codes will be generated from
+// Input that decodes to no key bytes (empty, spaces or padding only) is rejected:
+// an empty HMAC key still "works", but anyone can compute its codes
+fn decode_secret(secret: &str) -> napi::Result<Vec<u8>> {
+    match decode_base32(secret) {
+        Some(bytes) if bytes.is_empty() => Err(napi::Error::from_reason("Secret is empty")),
+        Some(bytes) => Ok(bytes),
+        None => Err(napi::Error::from_reason("Invalid Base32 encoding")),
+    }
+}
+
+// The form every secret is stored in: unpadded uppercase base32 without spaces
+// Equal keys always give identical strings, however they were imported
+#[napi]
+pub fn to_canonical_storage(secret: &SecureSecret) -> napi::Result<String> {
+    Ok(BASE32_NOPAD.encode(secret.bytes()?))
+}
+
 #[napi]
 pub fn generate_totp(secret: &SecureSecret, timestamp: Option<i64>) -> napi::Result<String> {
-    let time = timestamp.unwrap_or_else(|| std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64);
+    let time = timestamp.unwrap_or_else(current_timestamp);
     
     // Calculate time counter: floor(timestamp / period)
-    let counter = (time as u64) / TOTP_PERIOD;
+    let counter = time_counter(time, TOTP_PERIOD as u32, 0)?;
+    
+    hotp_code(secret.bytes()?, counter as u64, TOTP_DIGITS as u32, HashAlgorithm::Sha1)
+}
+
+// Optional TOTP parameters; anything left out uses the RFC 6238 defaults
+#[napi(object)]
+pub struct TotpParams {
+    pub digits: Option<u32>,
+    pub period: Option<u32>,
+    pub algorithm: Option<String>,
+    pub t0: Option<i64>,
+}
+
+// TOTP parameters with defaults applied and values validated
+#[derive(Clone, Copy, Debug)]
+struct ResolvedParams {
+    digits: u32,
+    period: u32,
+    algorithm: HashAlgorithm,
+    t0: i64,
+}
+
+impl ResolvedParams {
+    fn resolve(params: Option<TotpParams>) -> napi::Result<Self> {
+        let params = params.unwrap_or(TotpParams {
+            digits: None,
+            period: None,
+            algorithm: None,
+            t0: None,
+        });
+        
+        let digits = params.digits.unwrap_or(TOTP_DIGITS as u32);
+        validate_digits(digits)?;
+        
+        let period = params.period.unwrap_or(TOTP_PERIOD as u32);
+        if period == 0 {
+            return Err(napi::Error::from_reason("Invalid period: must be greater than zero"));
+        }
+        
+        let algorithm = match params.algorithm {
+            Some(name) => HashAlgorithm::from_name(&name)?,
+            None => HashAlgorithm::Sha1,
+        };
+        
+        Ok(Self {
+            digits,
+            period,
+            algorithm,
+            t0: params.t0.unwrap_or(0),
+        })
+    }
+}
+
+// Outcome of a verification, including which window offset matched
+#[napi(object)]
+pub struct VerifyResult {
+    pub valid: bool,
+    // -1 means the code belongs to the previous window (device clock ahead),
+    // +1 to the next window (device clock behind); absent when invalid
+    pub matched_offset: Option<i32>,
+}
+
+// Verify a code within +/- `window` steps and report which step matched
+// Every offset is checked with a constant-time comparison and without early exit,
+// so the app can watch for persistent non-zero offsets to detect clock skew
+#[napi]
+pub fn verify_totp_verbose(
+    secret: &SecureSecret,
+    code: String,
+    params: Option<TotpParams>,
+    window: u32,
+    timestamp: Option<i64>,
+) -> napi::Result<VerifyResult> {
+    let params = ResolvedParams::resolve(params)?;
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    let counter = time_counter(time, params.period, params.t0)?;
+    
+    let matched_offset = matching_offset(secret.bytes()?, &code, counter, window, &params)?;
+    
+    Ok(VerifyResult {
+        valid: matched_offset.is_some(),
+        matched_offset: matched_offset.map(|offset| offset as i32),
+    })
+}
+
+// Outcome of `verify_totp_no_replay`
+#[napi(object)]
+pub struct ReplayCheckResult {
+    pub valid: bool,
+    // Window offset of the accepted step, as in `VerifyResult`; absent when invalid
+    pub matched_offset: Option<i32>,
+    // Counter of the accepted step; store it as the next `last_accepted_counter`
+    pub counter: Option<i64>,
+    // The code is genuine but its step was already used (or is older than one that was)
+    pub replayed: bool,
+}
+
+// Verify a code within +/- `window` steps, accepting each step at most once
+// Steps at or before `last_accepted_counter` are refused even when the code
+// matches, so a code cannot be used twice within its window, nor can an older
+// one after a newer was accepted. Pass None for a user's first verification
+// and persist the returned `counter` before reporting success.
+#[napi]
+pub fn verify_totp_no_replay(
+    secret: &SecureSecret,
+    code: String,
+    last_accepted_counter: Option<i64>,
+    params: Option<TotpParams>,
+    window: u32,
+    timestamp: Option<i64>,
+) -> napi::Result<ReplayCheckResult> {
+    let params = ResolvedParams::resolve(params)?;
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    let counter = time_counter(time, params.period, params.t0)?;
+    let secret = secret.bytes()?;
+    
+    // Like `matching_offset`, every step is compared without early exit
+    let mut accepted = None;
+    let mut replayed = false;
+    for offset in -(window as i64)..=(window as i64) {
+        let step = counter.saturating_add(offset);
+        if step < 0 {
+            continue;
+        }
+        
+        let expected = hotp_code(secret, step as u64, params.digits, params.algorithm)?;
+        if constant_time::verify_slices_are_equal(expected.as_bytes(), code.as_bytes()).is_ok() {
+            if last_accepted_counter.is_some_and(|last| step <= last) {
+                replayed = true;
+            } else if accepted.is_none() {
+                accepted = Some(offset);
+            }
+        }
+    }
+    
+    Ok(ReplayCheckResult {
+        valid: accepted.is_some(),
+        matched_offset: accepted.map(|offset| offset as i32),
+        counter: accepted.map(|offset| counter + offset),
+        replayed: replayed && accepted.is_none(),
+    })
+}
+
+// Verification outcome together with how long the code will still be accepted
+#[napi(object)]
+pub struct VerifyReport {
+    pub valid: bool,
+    // Window offset of the match, as in `VerifyResult`; absent when invalid
+    pub offset: Option<i32>,
+    // Seconds until the matched code drops out of the accepted window; absent when invalid
+    pub remaining_seconds: Option<u32>,
+}
+
+// Verify a code and report its remaining validity from a single clock reading,
+// so the auto-fill path needs one call and both answers agree on the time
+// A code matched at offset k stays accepted until its step is more than `window`
+// steps in the past; with `window` 0 this is the usual countdown
+#[napi]
+pub fn verify_and_report(
+    secret: &SecureSecret,
+    code: String,
+    params: Option<TotpParams>,
+    window: u32,
+    timestamp: Option<i64>,
+) -> napi::Result<VerifyReport> {
+    let params = ResolvedParams::resolve(params)?;
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    let counter = time_counter(time, params.period, params.t0)?;
+    
+    let offset = matching_offset(secret.bytes()?, &code, counter, window, &params)?;
+    let remaining_seconds = offset.map(|offset| {
+        let last_accepted = counter.saturating_add(offset).saturating_add(window as i64);
+        let expires_at = last_accepted
+            .saturating_add(1)
+            .saturating_mul(params.period as i64)
+            .saturating_add(params.t0);
+        expires_at.saturating_sub(time).clamp(0, u32::MAX as i64) as u32
+    });
+    
+    Ok(VerifyReport {
+        valid: offset.is_some(),
+        offset: offset.map(|offset| offset as i32),
+        remaining_seconds,
+    })
+}
+
+// One code to check in `verify_totp_batch`
+#[napi(object)]
+pub struct VerifyRequest {
+    // Base32 secret of the user the code belongs to
+    pub secret: String,
+    pub code: String,
+    pub params: Option<TotpParams>,
+    // Steps accepted either side; defaults to 1
+    pub window: Option<u32>,
+}
+
+const BATCH_DEFAULT_WINDOW: u32 = 1;
+
+// Verify many users' codes in one call, for servers embedding the crate
+// All entries are checked against the same clock reading. A bad secret or
+// invalid parameters make only that entry false; the batch itself never fails.
+#[napi]
+pub fn verify_totp_batch(requests: Vec<VerifyRequest>, timestamp: Option<i64>) -> Vec<bool> {
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    requests
+        .into_iter()
+        .map(|request| verify_request(request, time).unwrap_or(false))
+        .collect()
+}
+
+fn verify_request(request: VerifyRequest, time: i64) -> napi::Result<bool> {
+    let VerifyRequest { mut secret, code, params, window } = request;
+    let offset = ResolvedParams::resolve(params).and_then(|params| {
+        let counter = time_counter(time, params.period, params.t0)?;
+        let mut bytes = decode_secret(&secret)?;
+        let offset = matching_offset(&bytes, &code, counter, window.unwrap_or(BATCH_DEFAULT_WINDOW), &params);
+        bytes.zeroize();
+        offset
+    });
+    secret.zeroize();
+    Ok(offset?.is_some())
+}
+
+// Check a code against the `2 * radius + 1` counters around `center_counter`
+// and return the offset of the matching one, for verifiers that need wide tolerance
+// Every counter is computed and compared in constant time without early exit, so
+// timing does not reveal which window matched; the cost grows linearly with `radius`
+#[napi]
+pub fn code_in_windows(
+    secret: &SecureSecret,
+    code: String,
+    center_counter: i64,
+    radius: u32,
+    params: Option<TotpParams>,
+) -> napi::Result<Option<i64>> {
+    let params = ResolvedParams::resolve(params)?;
+    matching_offset(secret.bytes()?, &code, center_counter, radius, &params)
+}
+
+// Steps accepted either side when confirming a new account; wider than usual
+// because the user may take a while to read and type the first code
+const ENROLLMENT_WINDOW: u32 = 2;
+
+// Outcome of confirming a newly added account with a code from the user
+#[napi(object)]
+pub struct EnrollmentResult {
+    pub confirmed: bool,
+    // Window offset of the match; a non-zero value at setup points to clock skew
+    pub offset: Option<i32>,
+}
+
+// Check the first code a user enters for a new account, before saving it
+// `timestamp` defaults to the current time
+#[napi]
+pub fn confirm_enrollment(
+    secret: &SecureSecret,
+    user_entered_code: String,
+    params: Option<TotpParams>,
+    timestamp: Option<i64>,
+) -> napi::Result<EnrollmentResult> {
+    let params = ResolvedParams::resolve(params)?;
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    let counter = time_counter(time, params.period, params.t0)?;
+    let code = user_entered_code.replace(' ', "");
+    let offset = matching_offset(secret.bytes()?, &code, counter, ENROLLMENT_WINDOW, &params)?;
+    
+    Ok(EnrollmentResult {
+        confirmed: offset.is_some(),
+        offset: offset.map(|offset| offset as i32),
+    })
+}
+
+fn matching_offset(
+    secret: &[u8],
+    code: &str,
+    counter: i64,
+    radius: u32,
+    params: &ResolvedParams,
+) -> napi::Result<Option<i64>> {
+    let mut matched_offset = None;
+    for offset in -(radius as i64)..=(radius as i64) {
+        let step = counter.saturating_add(offset);
+        if step < 0 {
+            continue;
+        }
+        
+        let expected = hotp_code(secret, step as u64, params.digits, params.algorithm)?;
+        let matches = constant_time::verify_slices_are_equal(expected.as_bytes(), code.as_bytes()).is_ok();
+        if matches && matched_offset.is_none() {
+            matched_offset = Some(offset);
+        }
+    }
+    Ok(matched_offset)
+}
+
+// Code for a specific (future) moment together with the window it belongs to
+#[napi(object)]
+pub struct FutureCode {
+    pub code: String,
+    pub window_start: i64,
+    pub window_end: i64,
+}
+
+// Furthest ahead a code may be requested (roughly ten years)
+const MAX_FUTURE_SECONDS: i64 = 10 * 365 * 24 * 60 * 60;
+
+// Compute the code that will be valid at `target_time`, for scheduling integrations
+// `target_time` must be between now (`timestamp`, defaults to the current time)
+// and roughly ten years from now
+#[napi]
+pub fn code_valid_at_future(
+    secret: &SecureSecret,
+    target_time: i64,
+    params: Option<TotpParams>,
+    timestamp: Option<i64>,
+) -> napi::Result<FutureCode> {
+    let params = ResolvedParams::resolve(params)?;
     
-    // Create buffer for counter bytes (8 bytes, big-endian)
-    let mut counter_bytes = [0u8; 8];
-    for i in 0..8 {
-        counter_bytes[7 - i] = ((counter >> (i * 8)) & 0xff) as u8;
+    let now = timestamp.unwrap_or_else(current_timestamp);
+    if target_time < now {
+        return Err(napi::Error::from_reason("Target time is in the past"));
+    }
+    if target_time.saturating_sub(now) > MAX_FUTURE_SECONDS {
+        return Err(napi::Error::from_reason("Target time is too far in the future"));
     }
     
-    // Create HMAC-SHA1
-    let mut mac = <HmacSha1 as Mac>::new_from_slice(&secret.inner)
-        .map_err(|e| napi::Error::from_reason(format!("HMAC error: {}", e)))?;
+    let counter = time_counter(target_time, params.period, params.t0)?;
+    let window_start = params.t0 + counter * params.period as i64;
     
-    // Update HMAC with counter
-    mac.update(&counter_bytes);
+    Ok(FutureCode {
+        code: hotp_code(secret.bytes()?, counter as u64, params.digits, params.algorithm)?,
+        window_start,
+        window_end: window_start + params.period as i64,
+    })
+}
+
+// Whether two accounts show the same code at the moment, for answering
+// "two of my accounts have the same code" support reports
+// Checks every step within +/- `window`, comparing codes for the same step.
+// Distinct secrets are expected to coincide now and then: with 6 digits any two
+// accounts share a code about once in a million steps, and with many accounts
+// some pair matches far more often. It is harmless and ends with the step.
+// Two copies of the same secret always collide, which is not a coincidence.
+#[napi]
+pub fn secrets_collide_now(
+    secret_a: &SecureSecret,
+    secret_b: &SecureSecret,
+    params: Option<TotpParams>,
+    window: u32,
+    timestamp: Option<i64>,
+) -> napi::Result<bool> {
+    let params = ResolvedParams::resolve(params)?;
+    let time = timestamp.unwrap_or_else(current_timestamp);
+    let counter = time_counter(time, params.period, params.t0)?;
+    let (a, b) = (secret_a.bytes()?, secret_b.bytes()?);
     
-    // Finalize and get result
-    let hmac_result = mac.finalize().into_bytes();
+    for offset in -(window as i64)..=(window as i64) {
+        let step = counter.saturating_add(offset);
+        if step < 0 {
+            continue;
+        }
+        let code_a = hotp_code(a, step as u64, params.digits, params.algorithm)?;
+        let code_b = hotp_code(b, step as u64, params.digits, params.algorithm)?;
+        if code_a == code_b {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// HOTP value for a counter (RFC 4226), also the core of every TOTP variant
+fn hotp_code(secret: &[u8], counter: u64, digits: u32, algorithm: HashAlgorithm) -> napi::Result<String> {
+    let digest = hmac_digest(secret, &counter.to_be_bytes(), algorithm)?;
+    Ok(format_code(dynamic_truncate(&digest), digits))
+}
+
+// Steam Guard alphabet: digits and consonants that are hard to confuse
+const STEAM_ALPHABET: &[u8; 26] = b"23456789BCDFGHJKMNPQRTVWXY";
+const STEAM_CODE_LENGTH: usize = 5;
+
+// Steam Guard code: HMAC-SHA1 TOTP with the truncated value written in base 26
+fn steam_code(secret: &[u8], counter: u64) -> napi::Result<String> {
+    let digest = hmac_digest(secret, &counter.to_be_bytes(), HashAlgorithm::Sha1)?;
+    let mut value = dynamic_truncate(&digest);
     
-    // Dynamic truncation
-    let offset = (hmac_result[19] & 0xf) as usize;
-    let code = ((hmac_result[offset] & 0x7f) as u32) << 24
-        | ((hmac_result[offset + 1] & 0xff) as u32) << 16
-        | ((hmac_result[offset + 2] & 0xff) as u32) << 8
-        | ((hmac_result[offset + 3] & 0xff) as u32);
+    let mut code = String::with_capacity(STEAM_CODE_LENGTH);
+    for _ in 0..STEAM_CODE_LENGTH {
+        code.push(STEAM_ALPHABET[(value % 26) as usize] as char);
+        value /= 26;
+    }
+    Ok(code)
+}
+
+fn hmac_digest(key: &[u8], message: &[u8], algorithm: HashAlgorithm) -> napi::Result<Vec<u8>> {
+    fn compute<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> napi::Result<Vec<u8>> {
+        let mut mac = <M as Mac>::new_from_slice(key)
+            .map_err(|e| napi::Error::from_reason(format!("HMAC error: {}", e)))?;
+        mac.update(message);
+        let mut output = mac.finalize().into_bytes();
+        let digest = output.to_vec();
+        output.as_mut_slice().zeroize();
+        Ok(digest)
+    }
+    
+    match algorithm {
+        HashAlgorithm::Sha1 => compute::<HmacSha1>(key, message),
+        HashAlgorithm::Sha256 => compute::<Hmac<Sha256>>(key, message),
+        HashAlgorithm::Sha512 => compute::<Hmac<Sha512>>(key, message),
+    }
+}
+
+fn current_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+// Generate a TOTP code from an HMAC digest computed elsewhere (e.g. a secure
+// element that never releases the key). Only truncation and formatting happen here.
+#[napi]
+pub fn totp_from_hmac(hmac_result: Buffer, digits: u32, algorithm: String) -> napi::Result<String> {
+    let algorithm = HashAlgorithm::from_name(&algorithm)?;
+    validate_digits(digits)?;
+    
+    // The digest must be exactly the size produced by the declared algorithm
+    if hmac_result.len() != algorithm.digest_len() {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid HMAC length for {}: expected {} bytes, got {}",
+            algorithm.name(),
+            algorithm.digest_len(),
+            hmac_result.len()
+        )));
+    }
     
-    // Modulo and stringify
-    let code = (code % 10u32.pow(TOTP_DIGITS as u32)).to_string();
+    Ok(format_code(dynamic_truncate(&hmac_result), digits))
+}
+
+// HMAC of an arbitrary server challenge, for challenge-response schemes (e.g. OCRA, RFC 6287)
+// Returns the full digest; the caller decides how to truncate or compare it
+#[napi]
+pub fn hmac_challenge_response(secret: &SecureSecret, challenge: Buffer, algorithm: String) -> napi::Result<Buffer> {
+    challenge_response(secret, &challenge, &algorithm).map(Buffer::from)
+}
+
+fn challenge_response(secret: &SecureSecret, challenge: &[u8], algorithm: &str) -> napi::Result<Vec<u8>> {
+    let algorithm = HashAlgorithm::from_name(algorithm)?;
+    hmac_digest(secret.bytes()?, challenge, algorithm)
+}
+
+// Expose the 31-bit dynamic truncation result (before the decimal modulo), so
+// integrators can compare their implementation step by step with this one
+// Works on digests of any supported length, taking the offset from the last byte
+#[cfg(feature = "interop")]
+#[napi]
+pub fn dynamic_truncation(hmac: Buffer) -> napi::Result<u32> {
+    // Shortest digest in use (HMAC-SHA1)
+    const MIN_TRUNCATION_DIGEST_LENGTH: usize = 20;
     
-    // Pad with leading zeros if necessary
-    Ok(format!("{:0>width$}", code, width = TOTP_DIGITS))
+    if hmac.len() < MIN_TRUNCATION_DIGEST_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid HMAC length: expected at least {} bytes, got {}",
+            MIN_TRUNCATION_DIGEST_LENGTH,
+            hmac.len()
+        )));
+    }
+    Ok(dynamic_truncate(&hmac))
+}
+
+// RFC 4226 dynamic truncation: the low nibble of the last byte selects
+// a 4-byte window, whose top bit is masked off to give a 31-bit value
+fn dynamic_truncate(digest: &[u8]) -> u32 {
+    let offset = (digest[digest.len() - 1] & 0xf) as usize;
+    ((digest[offset] & 0x7f) as u32) << 24
+        | (digest[offset + 1] as u32) << 16
+        | (digest[offset + 2] as u32) << 8
+        | (digest[offset + 3] as u32)
+}
+
+// Reduce a truncated value to `digits` decimal digits, keeping leading zeros
+fn format_code(value: u32, digits: u32) -> String {
+    let code = value % 10u32.pow(digits);
+    format!("{:0>width$}", code, width = digits as usize)
+}
+
+// Text to put on the clipboard for a code: grouping spaces and dashes removed
+// ("012 345" -> "012345"), leading zeros kept. No invisible marker is added,
+// since websites reject pasted codes containing one; use `pasted_code_intact`
+// on the receiving side where a paste target may have treated it as a number.
+#[napi]
+pub fn copy_format(code: String) -> napi::Result<String> {
+    let formatted: String = code.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+    if formatted.is_empty() || !formatted.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(napi::Error::from_reason("Invalid code"));
+    }
+    Ok(formatted)
+}
+
+// Whether a pasted numeric code still has all `digits` digits; false when
+// leading zeros were lost ("123456" pasted for an 8-digit "00123456")
+#[napi]
+pub fn pasted_code_intact(pasted: String, digits: u32) -> bool {
+    let pasted = strip_decode_artifacts(&pasted);
+    let pasted = pasted.trim();
+    pasted.len() == digits as usize && pasted.chars().all(|c| c.is_ascii_digit())
+}
+
+fn validate_digits(digits: u32) -> napi::Result<()> {
+    if (MIN_DIGITS..=MAX_DIGITS).contains(&digits) {
+        Ok(())
+    } else {
+        Err(napi::Error::from_reason(format!(
+            "Invalid digits: {} (must be between {} and {})",
+            digits, MIN_DIGITS, MAX_DIGITS
+        )))
+    }
 }
 
 #[napi]
@@ -113,10 +754,62 @@ pub fn remaining_seconds() -> i32 {
     (next_window - now) as i32
 }
 
+// Start (inclusive) and end (exclusive) Unix times of a TOTP window
+#[napi(object)]
+pub struct WindowBounds {
+    pub start: i64,
+    pub end: i64,
+}
+
+// Exact boundaries of the TOTP window containing `timestamp`, for timeline rendering
+// Windows are counted from `t0`; times before `t0` belong to no window and are rejected
+#[napi]
+pub fn window_bounds(timestamp: i64, period: u32, t0: i64) -> napi::Result<WindowBounds> {
+    let counter = time_counter(timestamp, period, t0)?;
+    let start = t0 + counter * period as i64;
+    let end = start
+        .checked_add(period as i64)
+        .ok_or_else(|| napi::Error::from_reason("Timestamp is out of range"))?;
+    
+    Ok(WindowBounds { start, end })
+}
+
+// Time step counter used for a timestamp, for correlating codes with server logs
+// Timestamps before `t0` are rejected rather than producing a negative counter
+#[napi]
+pub fn timestamp_to_counter(timestamp: i64, period: u32, t0: i64) -> napi::Result<i64> {
+    time_counter(timestamp, period, t0)
+}
+
+// Number of new codes a time-based secret produces after `from_time` up to and
+// including `until_time` (Unix seconds), i.e. the window boundaries crossed
+// Both times in the same window gives 0; `until_time` before `from_time` is rejected
+#[napi]
+pub fn codes_until(period: u32, from_time: i64, until_time: i64) -> napi::Result<i64> {
+    if until_time < from_time {
+        return Err(napi::Error::from_reason("Invalid range: until_time is before from_time"));
+    }
+    Ok(time_counter(until_time, period, 0)? - time_counter(from_time, period, 0)?)
+}
+
+// RFC 6238 time step: floor((timestamp - t0) / period)
+fn time_counter(timestamp: i64, period: u32, t0: i64) -> napi::Result<i64> {
+    if period == 0 {
+        return Err(napi::Error::from_reason("Invalid period: must be greater than zero"));
+    }
+    
+    let elapsed = timestamp
+        .checked_sub(t0)
+        .filter(|elapsed| *elapsed >= 0)
+        .ok_or_else(|| napi::Error::from_reason("Timestamp is before the TOTP epoch (t0)"))?;
+    
+    Ok(elapsed / period as i64)
+}
+
 #[napi]
 pub fn validate_base32_secret(secret: String) -> bool {
     // Clean the input (remove spaces, uppercase)
-    let cleaned = secret.replace(' ', "").to_uppercase();
+    let cleaned = normalize_base32(&secret);
     
     // Check if it's valid base32
     if !cleaned.chars().all(|c| {
@@ -126,7 +819,69 @@ pub fn validate_base32_secret(secret: String) -> bool {
     }
     
     // Try decoding
-    BASE32.decode(cleaned.as_bytes()).is_ok()
+    match decode_secret(&cleaned) {
+        Ok(mut bytes) => {
+            bytes.zeroize();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// Best-guess classification of text pasted into the secret field
+#[napi(string_enum)]
+pub enum InputKind {
+    TotpSeed,
+    LikelyRecoveryCode,
+    OtpauthUri,
+    Unknown,
+}
+
+// Shortest seed we treat as a real TOTP key: 16 base32 characters (80 bits).
+// RFC 4226 requires at least 128 bits, but 80-bit keys are common in the wild.
+const MIN_SEED_CHARS: usize = 16;
+
+// Guess what kind of input the user pasted so the UI can warn about backup codes
+// This is heuristic: recovery codes are short, often digit-only or dash-grouped,
+// while seeds are long base32 strings (optionally space-grouped)
+#[napi]
+pub fn classify_secret_input(input: String) -> InputKind {
+    let trimmed = input.trim();
+    let lower = trimmed.to_lowercase();
+    if lower.starts_with("otpauth://") || lower.starts_with("otpauth-migration://") {
+        return InputKind::OtpauthUri;
+    }
+    
+    let compact: String = trimmed
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if compact.is_empty() || !compact.chars().all(|c| c.is_ascii_alphanumeric() || c == '=') {
+        return InputKind::Unknown;
+    }
+    
+    // Dash-separated groups ("a1b2c-3d4e5") and all-digit codes ("1234 5678")
+    // are typical backup code layouts and never valid seeds in practice
+    let dash_grouped = trimmed.contains('-');
+    let digits_only = compact.chars().all(|c| c.is_ascii_digit());
+    if (dash_grouped || digits_only) && compact.len() <= 20 {
+        return InputKind::LikelyRecoveryCode;
+    }
+    
+    // Only the alphabet is checked: many services omit base32 padding
+    let unpadded = compact.trim_end_matches('=').to_uppercase();
+    if unpadded.chars().all(|c| matches!(c, 'A'..='Z' | '2'..='7')) {
+        if unpadded.len() >= MIN_SEED_CHARS {
+            InputKind::TotpSeed
+        } else {
+            // Valid base32 but too short to be a usable key
+            InputKind::LikelyRecoveryCode
+        }
+    } else if compact.len() <= 20 {
+        InputKind::LikelyRecoveryCode
+    } else {
+        InputKind::Unknown
+    }
 }
 
 #[napi]
@@ -134,94 +889,371 @@ pub struct CryptoResult {
     pub data: String,
     pub success: bool,
     pub error: Option<String>,
+    // Machine-readable category of `error`, see `ErrorCode`
+    pub error_code: Option<String>,
+}
+
+impl CryptoResult {
+    fn success(data: String) -> Self {
+        Self {
+            data,
+            success: true,
+            error: None,
+            error_code: None,
+        }
+    }
+    
+    fn failure(error: CryptoError) -> Self {
+        Self {
+            data: String::new(),
+            success: false,
+            error: Some(error.message),
+            error_code: Some(error.code.as_str().to_string()),
+        }
+    }
+}
+
+// Failure categories shared by all encryption formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorCode {
+    // Input is not valid base64
+    InvalidEncoding,
+    // Input is too short or structurally malformed
+    InvalidFormat,
+    // Salt and iv are present but there is no room for the authentication tag
+    Truncated,
+    // AEAD tag check failed: wrong password or modified data
+    AuthenticationFailed,
+    // Decrypted bytes are not valid UTF-8
+    InvalidUtf8,
+    EncryptionFailed,
+    // A SecureSecret was used after `clear()`
+    SecretCleared,
+    // Password exceeds MAX_PASSWORD_LENGTH
+    PasswordTooLong,
+    // A time-locked blob was opened before its unlock time
+    TimeLocked,
+    // Strict decryption found control characters in the plaintext
+    SuspiciousPlaintext,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 10] = [
+        Self::InvalidEncoding,
+        Self::InvalidFormat,
+        Self::Truncated,
+        Self::AuthenticationFailed,
+        Self::InvalidUtf8,
+        Self::EncryptionFailed,
+        Self::SecretCleared,
+        Self::PasswordTooLong,
+        Self::TimeLocked,
+        Self::SuspiciousPlaintext,
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|code| code.as_str() == name)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidEncoding => "INVALID_ENCODING",
+            Self::InvalidFormat => "INVALID_FORMAT",
+            Self::Truncated => "TRUNCATED",
+            Self::AuthenticationFailed => "AUTHENTICATION_FAILED",
+            Self::InvalidUtf8 => "INVALID_UTF8",
+            Self::EncryptionFailed => "ENCRYPTION_FAILED",
+            Self::SecretCleared => "SECRET_CLEARED",
+            Self::PasswordTooLong => "PASSWORD_TOO_LONG",
+            Self::TimeLocked => "TIME_LOCKED",
+            Self::SuspiciousPlaintext => "SUSPICIOUS_PLAINTEXT",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CryptoError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl CryptoError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<CryptoError> for napi::Error {
+    fn from(error: CryptoError) -> Self {
+        napi::Error::from_reason(error.message)
+    }
+}
+
+// Concurrency guarantee for one exported operation
+#[napi(object)]
+pub struct ThreadSafetyEntry {
+    pub operation: String,
+    // Whether two calls may run at the same time on the same object
+    pub concurrent_safe: bool,
+    pub note: String,
+}
+
+// Document which operations may overlap on the same object
+// All exports are synchronous, so from JavaScript calls never overlap; this
+// matters once work is moved to worker threads or the libuv threadpool
+#[napi]
+pub fn thread_safety_info() -> Vec<ThreadSafetyEntry> {
+    [
+        ("generate_totp", true, "Reads the secret only"),
+        ("verify_totp_verbose", true, "Reads the secret only"),
+        ("verify_and_report", true, "Reads the secret only"),
+        ("verify_totp_no_replay", true, "Reads the secret only; serialize storing the returned counter per user"),
+        ("code_in_windows", true, "Reads the secret only"),
+        ("code_valid_at_future", true, "Reads the secret only"),
+        ("hmac_challenge_response", true, "Reads the secret only"),
+        ("generate_ocra", true, "Reads the secret only"),
+        ("to_canonical_storage", true, "Reads the secret only"),
+        ("SecureSecret.clear", false, "Wipes the secret; must not overlap with any use of it"),
+        ("RatchetEncryptor.next_entry", false, "Advances the chain key; calls must be serialized"),
+        ("decrypt_ratchet_entry", true, "Stateless"),
+        ("TransferOffer.public_key", true, "Reads the offer only"),
+        ("TransferOffer.complete_transfer", false, "Consumes the ephemeral key; succeeds at most once"),
+        ("accept_transfer_offer", true, "Stateless"),
+        ("encrypt_with_key", true, "Every record draws its own random nonce"),
+        ("decrypt_with_key", true, "Reads the key only"),
+        ("encrypt_record_with_key", true, "Reads the key only; distinct indices per base nonce are the caller's duty"),
+        ("decrypt_record_with_key", true, "Reads the key only"),
+        ("encrypt_accounts_parallel", true, "Spreads work over threads itself; nonces as for encrypt_with_key"),
+        ("save_changed_accounts", true, "Nonces as for encrypt_with_key"),
+        ("wrap_dek_with_external", false, "Calls back into JS; only from the JS thread"),
+        ("unwrap_dek_with_external", false, "Calls back into JS; only from the JS thread"),
+        ("derive_key_with_progress", true, "Derives on the thread pool; progress is queued to the JS thread"),
+        ("append_audit_entry", false, "Returns a new log; appends to one log must be serialized or entries are lost"),
+        ("verify_audit_log", true, "Stateless"),
+        ("read_audit_log", true, "Stateless"),
+        ("encrypt_data", true, "Stateless"),
+        ("decrypt_data", true, "Stateless"),
+    ]
+    .iter()
+    .map(|(operation, concurrent_safe, note)| ThreadSafetyEntry {
+        operation: operation.to_string(),
+        concurrent_safe: *concurrent_safe,
+        note: note.to_string(),
+    })
+    .collect()
+}
+
+// Vault blob format versions this build reads and writes, oldest first; a build
+// that handles a version handles every earlier one too, which `negotiate_format` relies on
+// 1: salt + iv + ciphertext (`encrypt_data`)
+// 2: creation/modification header + the version 1 layout (`encrypt_data_stamped`)
+const BLOB_FORMAT_VERSIONS: [u32; 2] = [1, 2];
+
+// Version and format details of this native module, for support logs and
+// feature detection in the frontend
+#[napi(object)]
+pub struct CrateInfo {
+    pub version: String,
+    // Entries of `BLOB_FORMAT_VERSIONS`
+    pub blob_formats: Vec<u32>,
+    pub otpauth_parameters: Vec<String>,
+    // e.g. "PBKDF2-SHA256", the same spelling `BlobInfo.kdf` expects
+    pub kdf: String,
+    pub kdf_iterations: u32,
+    pub cipher: String,
+}
+
+#[napi]
+pub fn crate_info() -> CrateInfo {
+    CrateInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        blob_formats: BLOB_FORMAT_VERSIONS.to_vec(),
+        otpauth_parameters: otpauth::SUPPORTED_PARAMETERS.iter().map(|name| name.to_string()).collect(),
+        kdf: "PBKDF2-SHA256".to_string(),
+        kdf_iterations: PBKDF2_ITERATIONS,
+        cipher: "AES-256-GCM".to_string(),
+    }
+}
+
+// Compile-time checks backing `thread_safety_info`: read-only objects must be
+// shareable across threads and mutable ones must at least be movable
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    const fn assert_send<T: Send>() {}
+    assert_send_sync::<SecureSecret>();
+    assert_send_sync::<keys::DerivedKey>();
+    assert_send::<ratchet::RatchetEncryptor>();
+    assert_send::<transfer::TransferOffer>();
+};
+
+// Output length marker required by ring's HKDF API
+struct OkmLength(usize);
+
+impl hkdf::KeyType for OkmLength {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+// HKDF-SHA256 extract-and-expand filling `out` completely
+fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8], out: &mut [u8]) -> napi::Result<()> {
+    hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+        .extract(ikm)
+        .expand(&[info], OkmLength(out.len()))
+        .and_then(|okm| okm.fill(out))
+        .map_err(|_| napi::Error::from_reason("Key derivation failed"))
 }
 
 #[napi]
 pub fn encrypt_data(data: String, password: String) -> CryptoResult {
+    match encrypt_bytes(data.as_bytes(), &password) {
+        // Encode as base64
+        Ok(result) => CryptoResult::success(base64::encode(&result)),
+        Err(error) => CryptoResult::failure(error),
+    }
+}
+
+#[napi]
+pub fn decrypt_data(encrypted_data: String, password: String) -> CryptoResult {
+    // Decode base64
+    let encrypted = match base64::decode(&encrypted_data) {
+        Ok(data) => data,
+        Err(e) => {
+            return CryptoResult::failure(CryptoError::new(
+                ErrorCode::InvalidEncoding,
+                format!("Base64 decode error: {}", e),
+            ))
+        }
+    };
+    
+    let plaintext = match decrypt_bytes(&encrypted, &password) {
+        Ok(plaintext) => plaintext,
+        Err(error) => return CryptoResult::failure(error),
+    };
+    
+    // Convert plaintext to string
+    match String::from_utf8(plaintext) {
+        Ok(data) => CryptoResult::success(data),
+        Err(e) => CryptoResult::failure(CryptoError::new(
+            ErrorCode::InvalidUtf8,
+            format!("UTF-8 decode error: {}", e),
+        )),
+    }
+}
+
+// `decrypt_data` that also rejects plaintext containing NUL or other control
+// characters (C0, DEL and C1, except tab, newline and carriage return)
+// Vault JSON never contains them, so their presence means a format bug produced
+// output that authenticated but is not what was encrypted. Opt-in, because
+// `decrypt_data` itself accepts arbitrary text.
+#[napi]
+pub fn decrypt_data_strict(encrypted_data: String, password: String) -> CryptoResult {
+    let mut result = decrypt_data(encrypted_data, password);
+    if result.success && result.data.chars().any(is_unexpected_control) {
+        result.data.zeroize();
+        return CryptoResult::failure(CryptoError::new(
+            ErrorCode::SuspiciousPlaintext,
+            "Decrypted data contains unexpected control characters",
+        ));
+    }
+    result
+}
+
+fn is_unexpected_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+// Password-based AES-256-GCM encryption shared by every blob format
+// Output layout: salt + iv + ciphertext (with GCM tag)
+fn encrypt_bytes(data: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    encrypt_bytes_bound(data, password, &[])
+}
+
+// `encrypt_bytes` that also authenticates `context` (appended to the salt in the AAD)
+// The context is not stored; decryption must supply the same bytes
+fn encrypt_bytes_bound(data: &[u8], password: &str, context: &[u8]) -> Result<Vec<u8>, CryptoError> {
     // Generate salt and iv
     let mut salt = [0u8; SALT_LENGTH];
     let mut iv = [0u8; IV_LENGTH];
     
     // Use ring's secure random number generator
     let rng = ring::rand::SystemRandom::new();
-    rng.fill(&mut salt).unwrap();
-    rng.fill(&mut iv).unwrap();
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut iv))
+        .map_err(|_| {
+            CryptoError::new(ErrorCode::EncryptionFailed, "Encryption error: failed to generate random values")
+        })?;
     
+    encrypt_bytes_with(data, password, &salt, &iv, context)
+}
+
+// `encrypt_bytes` with caller-chosen salt and iv
+// Both must be fresh random values except for reproducible test fixtures
+fn encrypt_bytes_with(
+    data: &[u8],
+    password: &str,
+    salt: &[u8; SALT_LENGTH],
+    iv: &[u8; IV_LENGTH],
+    context: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
     // Derive key using PBKDF2
-    let mut key_bytes = [0u8; 32]; // 256 bits
-    pbkdf2::derive(
-        pbkdf2::PBKDF2_HMAC_SHA256,
-        std::num::NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
-        &salt,
-        password.as_bytes(),
-        &mut key_bytes,
-    );
+    let mut key_bytes = derive_key(password, salt)?;
     
     // Create AES-GCM cipher
     let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
     let cipher = Aes256Gcm::new(key);
-    let nonce = Nonce::from_slice(&iv);
+    let nonce = Nonce::from_slice(iv);
     
-    // Create authenticated data (AAD): salt
-    let aad = salt.to_vec();
+    // Create authenticated data (AAD): salt + context
+    let aad = [salt.as_slice(), context].concat();
     
     // Encrypt
-    match cipher.encrypt(
+    let result = cipher.encrypt(
         nonce,
         Payload {
-            msg: data.as_bytes(),
+            msg: data,
             aad: &aad,
         },
-    ) {
-        Ok(ciphertext) => {
-            // Clear sensitive data from memory
-            key_bytes.zeroize();
-            
-            // Combine salt + iv + ciphertext
-            let mut result = Vec::with_capacity(salt.len() + iv.len() + ciphertext.len());
-            result.extend_from_slice(&salt);
-            result.extend_from_slice(&iv);
-            result.extend_from_slice(&ciphertext);
-            
-            // Encode as base64
-            CryptoResult {
-                data: base64::encode(&result),
-                success: true,
-                error: None,
-            }
-        }
-        Err(e) => {
-            key_bytes.zeroize();
-            CryptoResult {
-                data: String::new(),
-                success: false,
-                error: Some(format!("Encryption error: {}", e)),
-            }
-        }
-    }
+    );
+    
+    // Clear sensitive data from memory
+    key_bytes.zeroize();
+    
+    let ciphertext = result
+        .map_err(|e| CryptoError::new(ErrorCode::EncryptionFailed, format!("Encryption error: {}", e)))?;
+    
+    // Combine salt + iv + ciphertext
+    let mut output = Vec::with_capacity(salt.len() + iv.len() + ciphertext.len());
+    output.extend_from_slice(salt);
+    output.extend_from_slice(iv);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
 }
 
-#[napi]
-pub fn decrypt_data(encrypted_data: String, password: String) -> CryptoResult {
-    // Decode base64
-    let encrypted = match base64::decode(&encrypted_data) {
-        Ok(data) => data,
-        Err(e) => {
-            return CryptoResult {
-                data: String::new(),
-                success: false,
-                error: Some(format!("Base64 decode error: {}", e)),
-            }
-        }
-    };
+// Inverse of `encrypt_bytes`
+fn decrypt_bytes(encrypted: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    decrypt_bytes_bound(encrypted, password, &[])
+}
+
+// Inverse of `encrypt_bytes_bound`
+fn decrypt_bytes_bound(encrypted: &[u8], password: &str, context: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    // Never run an unencrypted vault through decryption, even if it was re-encoded
+    if encrypted.starts_with(plain::PLAIN_MAGIC) {
+        return Err(CryptoError::new(ErrorCode::InvalidFormat, "Vault is stored unencrypted"));
+    }
     
     // Check if the data is long enough
     if encrypted.len() < SALT_LENGTH + IV_LENGTH {
-        return CryptoResult {
-            data: String::new(),
-            success: false,
-            error: Some("Invalid encrypted data format".to_string()),
-        };
+        return Err(CryptoError::new(ErrorCode::InvalidFormat, "Invalid encrypted data format"));
+    }
+    
+    // Even empty plaintext leaves a full tag, so anything shorter was cut off;
+    // catch it here instead of reporting a wrong password
+    if encrypted.len() < SALT_LENGTH + IV_LENGTH + TAG_LENGTH {
+        return Err(CryptoError::new(ErrorCode::Truncated, "Encrypted data is truncated"));
     }
     
     // Extract salt, iv, and ciphertext
@@ -230,56 +1262,611 @@ pub fn decrypt_data(encrypted_data: String, password: String) -> CryptoResult {
     let ciphertext = &encrypted[SALT_LENGTH + IV_LENGTH..];
     
     // Derive key using PBKDF2
-    let mut key_bytes = [0u8; 32]; // 256 bits
-    pbkdf2::derive(
-        pbkdf2::PBKDF2_HMAC_SHA256,
-        std::num::NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
-        salt,
-        password.as_bytes(),
-        &mut key_bytes,
-    );
+    let mut key_bytes = derive_key(password, salt)?;
     
     // Create AES-GCM cipher
     let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
     let cipher = Aes256Gcm::new(key);
     let nonce = Nonce::from_slice(iv);
     
-    // Create authenticated data (AAD): salt
-    let aad = salt.to_vec();
+    // Create authenticated data (AAD): salt + context
+    let aad = [salt, context].concat();
     
     // Decrypt
-    match cipher.decrypt(
+    let result = cipher.decrypt(
         nonce,
         Payload {
             msg: ciphertext,
             aad: &aad,
         },
-    ) {
-        Ok(plaintext) => {
-            // Clear sensitive data from memory
-            key_bytes.zeroize();
-            
-            // Convert plaintext to string
-            match String::from_utf8(plaintext) {
-                Ok(data) => CryptoResult {
-                    data,
-                    success: true,
-                    error: None,
-                },
-                Err(e) => CryptoResult {
-                    data: String::new(),
-                    success: false,
-                    error: Some(format!("UTF-8 decode error: {}", e)),
-                },
-            }
+    );
+    
+    // Clear sensitive data from memory
+    key_bytes.zeroize();
+    
+    // The salt is both the KDF input and the AAD, so a tampered salt, iv,
+    // ciphertext or tag fails here exactly like a wrong password does. GCM
+    // cannot tell these apart without the right key, so the error names both.
+    result.map_err(|_| {
+        CryptoError::new(
+            ErrorCode::AuthenticationFailed,
+            "Decryption failed: wrong password or corrupted data",
+        )
+    })
+}
+
+// PBKDF2-HMAC-SHA256 key derivation for the vault key
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], CryptoError> {
+    if password.len() > MAX_PASSWORD_LENGTH {
+        return Err(CryptoError::new(
+            ErrorCode::PasswordTooLong,
+            format!("Password is too long: at most {} bytes are supported", MAX_PASSWORD_LENGTH),
+        ));
+    }
+    
+    let mut key_bytes = [0u8; 32]; // 256 bits
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        std::num::NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut key_bytes,
+    );
+    Ok(key_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::HEXLOWER;
+
+    use super::*;
+
+    #[test]
+    fn window_bounds_at_boundaries() {
+        let first = window_bounds(0, 30, 0).unwrap();
+        assert_eq!((first.start, first.end), (0, 30));
+
+        let last_second = window_bounds(29, 30, 0).unwrap();
+        assert_eq!((last_second.start, last_second.end), (0, 30));
+
+        // The end is exclusive: it starts the next window
+        let next = window_bounds(30, 30, 0).unwrap();
+        assert_eq!((next.start, next.end), (30, 60));
+
+        let shifted = window_bounds(100, 60, 10).unwrap();
+        assert_eq!((shifted.start, shifted.end), (70, 130));
+    }
+
+    #[test]
+    fn window_bounds_rejects_invalid_input() {
+        assert!(window_bounds(5, 30, 10).is_err());
+        assert!(window_bounds(5, 0, 0).is_err());
+        assert!(window_bounds(i64::MAX, 1, 0).is_err());
+    }
+
+    // RFC 6238 appendix B seed for HMAC-SHA1
+    fn rfc_secret() -> SecureSecret {
+        SecureSecret::from_bytes(b"12345678901234567890".to_vec())
+    }
+
+    fn eight_digits() -> Option<TotpParams> {
+        Some(TotpParams {
+            digits: Some(8),
+            period: None,
+            algorithm: None,
+            t0: None,
+        })
+    }
+
+    #[test]
+    fn code_valid_at_future_matches_rfc_vector() {
+        let future = code_valid_at_future(&rfc_secret(), 59, eight_digits(), Some(0)).unwrap();
+        assert_eq!(future.code, "94287082");
+        assert_eq!((future.window_start, future.window_end), (30, 60));
+
+        let later = code_valid_at_future(&rfc_secret(), 1_111_111_109, eight_digits(), Some(1_000_000_000)).unwrap();
+        assert_eq!(later.code, "07081804");
+    }
+
+    #[test]
+    fn code_valid_at_future_rejects_out_of_range_targets() {
+        let now = 1_700_000_000;
+        assert!(code_valid_at_future(&rfc_secret(), now - 1, None, Some(now)).is_err());
+        assert!(code_valid_at_future(&rfc_secret(), now, None, Some(now)).is_ok());
+        assert!(code_valid_at_future(&rfc_secret(), now + MAX_FUTURE_SECONDS, None, Some(now)).is_ok());
+        assert!(code_valid_at_future(&rfc_secret(), now + MAX_FUTURE_SECONDS + 1, None, Some(now)).is_err());
+    }
+
+    #[test]
+    fn timestamp_to_counter_known_pairs() {
+        // RFC 6238 appendix B times and their T values
+        for (time, counter) in [
+            (59, 0x1),
+            (1_111_111_109, 0x23523EC),
+            (1_111_111_111, 0x23523ED),
+            (1_234_567_890, 0x273EF07),
+            (2_000_000_000, 0x3F940AA),
+            (20_000_000_000, 0x27BC86AA),
+        ] {
+            assert_eq!(timestamp_to_counter(time, 30, 0).unwrap(), counter);
         }
-        Err(e) => {
-            key_bytes.zeroize();
-            CryptoResult {
-                data: String::new(),
-                success: false,
-                error: Some(format!("Decryption error (possibly wrong password): {}", e)),
+        assert_eq!(timestamp_to_counter(29, 30, 0).unwrap(), 0);
+        assert_eq!(timestamp_to_counter(30, 30, 0).unwrap(), 1);
+        assert_eq!(timestamp_to_counter(130, 60, 10).unwrap(), 2);
+        assert!(timestamp_to_counter(9, 60, 10).is_err());
+    }
+
+    #[test]
+    fn parallel_code_generation_on_a_shared_secret() {
+        let secret = rfc_secret();
+        let times: Vec<i64> = (0..200).map(|step| 1_700_000_000 + step * 30).collect();
+        let expected: Vec<String> = times.iter().map(|&time| generate_totp(&secret, Some(time)).unwrap()).collect();
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        times
+                            .iter()
+                            .map(|&time| generate_totp(&secret, Some(time)).unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for worker in workers {
+                assert_eq!(worker.join().unwrap(), expected);
             }
+        });
+    }
+
+    #[test]
+    fn thread_safety_info_has_no_duplicates() {
+        let entries = thread_safety_info();
+        let mut operations: Vec<_> = entries.iter().map(|entry| entry.operation.as_str()).collect();
+        operations.sort_unstable();
+        operations.dedup();
+        assert_eq!(operations.len(), entries.len());
+    }
+
+    // RFC 4226 Appendix D codes for counters 0..=9
+    const HOTP_CODES: [&str; 10] = [
+        "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489",
+    ];
+
+    #[test]
+    fn code_in_windows_radius_zero_checks_only_the_center() {
+        let secret = rfc_secret();
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[5].to_string(), 5, 0, None).unwrap(), Some(0));
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[4].to_string(), 5, 0, None).unwrap(), None);
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[6].to_string(), 5, 0, None).unwrap(), None);
+    }
+
+    #[test]
+    fn code_in_windows_reports_the_matching_offset() {
+        let secret = rfc_secret();
+        for (counter, code) in HOTP_CODES.iter().enumerate().skip(2).take(7) {
+            let offset = code_in_windows(&secret, code.to_string(), 5, 3, None).unwrap();
+            assert_eq!(offset, Some(counter as i64 - 5));
+        }
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[1].to_string(), 5, 3, None).unwrap(), None);
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[9].to_string(), 5, 3, None).unwrap(), None);
+        assert_eq!(code_in_windows(&secret, "12345".to_string(), 5, 3, None).unwrap(), None);
+    }
+
+    #[test]
+    fn code_in_windows_skips_negative_counters() {
+        let secret = rfc_secret();
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[0].to_string(), 1, 4, None).unwrap(), Some(-1));
+        assert_eq!(code_in_windows(&secret, HOTP_CODES[0].to_string(), 0, 2, None).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn password_length_limit_is_inclusive() {
+        let salt = [7u8; SALT_LENGTH];
+        assert!(derive_key(&"a".repeat(MAX_PASSWORD_LENGTH), &salt).is_ok());
+        let error = derive_key(&"a".repeat(MAX_PASSWORD_LENGTH + 1), &salt).unwrap_err();
+        assert_eq!(error.code, ErrorCode::PasswordTooLong);
+    }
+
+    #[test]
+    fn password_length_limit_counts_bytes() {
+        // Two bytes per character in UTF-8
+        let salt = [7u8; SALT_LENGTH];
+        assert!(derive_key(&"é".repeat(MAX_PASSWORD_LENGTH / 2), &salt).is_ok());
+        let error = derive_key(&"é".repeat(MAX_PASSWORD_LENGTH / 2 + 1), &salt).unwrap_err();
+        assert_eq!(error.code, ErrorCode::PasswordTooLong);
+    }
+
+    #[test]
+    fn oversized_password_is_rejected_before_encrypting() {
+        let result = encrypt_data("vault".to_string(), "a".repeat(MAX_PASSWORD_LENGTH + 1));
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some("PASSWORD_TOO_LONG"));
+    }
+
+    #[test]
+    fn confirm_enrollment_accepts_codes_within_two_steps() {
+        let secret = rfc_secret();
+        let confirm = |code: &str, time: i64| {
+            confirm_enrollment(&secret, code.to_string(), eight_digits(), Some(time)).unwrap()
+        };
+
+        let exact = confirm("94287082", 59);
+        assert!(exact.confirmed);
+        assert_eq!(exact.offset, Some(0));
+        // Spaces from copying the code are ignored
+        assert_eq!(confirm("9428 7082", 59).offset, Some(0));
+        // The device clock is two steps ahead of the code
+        assert_eq!(confirm("94287082", 119).offset, Some(-2));
+        assert_eq!(confirm("94287082", 0).offset, Some(1));
+
+        let late = confirm("94287082", 149);
+        assert!(!late.confirmed);
+        assert_eq!(late.offset, None);
+        assert!(!confirm("00000000", 59).confirmed);
+    }
+
+    #[test]
+    fn blob_without_room_for_a_tag_is_truncated() {
+        let blob = vec![0u8; SALT_LENGTH + IV_LENGTH + TAG_LENGTH - 1];
+        assert_eq!(decrypt_bytes(&blob, "hunter2").unwrap_err().code, ErrorCode::Truncated);
+
+        let result = decrypt_data(base64::encode(&blob), "hunter2".to_string());
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some("TRUNCATED"));
+
+        // One more byte is long enough to be tried, and fails authentication instead
+        let blob = vec![0u8; SALT_LENGTH + IV_LENGTH + TAG_LENGTH];
+        assert_eq!(decrypt_bytes(&blob, "hunter2").unwrap_err().code, ErrorCode::AuthenticationFailed);
+    }
+
+    #[test]
+    fn tampered_salt_is_an_authentication_failure() {
+        let encrypted = encrypt_data("vault".to_string(), "hunter2".to_string());
+        assert!(encrypted.success);
+        let mut blob = base64::decode(&encrypted.data).unwrap();
+        blob[0] ^= 0x01;
+
+        let result = decrypt_data(base64::encode(&blob), "hunter2".to_string());
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some(ErrorCode::AuthenticationFailed.as_str()));
+        assert_eq!(decrypt_bytes(&blob, "hunter2").unwrap_err().code, ErrorCode::AuthenticationFailed);
+    }
+
+    #[test]
+    fn canonical_storage_is_the_same_for_every_encoding() {
+        let imports = [
+            SecureSecret::new("JBSWY3DPEHPK3PXP".to_string()).unwrap(),
+            SecureSecret::new("jbsw y3dp ehpk 3pxp".to_string()).unwrap(),
+            SecureSecret::from_hex("48656c6c6f21deadbeef".to_string()).unwrap(),
+            SecureSecret::from_hex("48 65 6C 6C 6F 21 DE AD BE EF".to_string()).unwrap(),
+            SecureSecret::from_base64("SGVsbG8h3q2+7w==".to_string()).unwrap(),
+            SecureSecret::from_base64("SGVsbG8h3q2+7w".to_string()).unwrap(),
+        ];
+        for secret in &imports {
+            assert_eq!(to_canonical_storage(secret).unwrap(), "JBSWY3DPEHPK3PXP");
+        }
+    }
+
+    #[test]
+    fn canonical_storage_is_unpadded_uppercase_base32() {
+        let short = SecureSecret::from_hex("3132".to_string()).unwrap();
+        assert_eq!(to_canonical_storage(&short).unwrap(), "GEZA");
+        assert_eq!(to_canonical_storage(&rfc_secret()).unwrap(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+
+        let mut cleared = rfc_secret();
+        cleared.clear();
+        assert!(to_canonical_storage(&cleared).is_err());
+    }
+
+    #[test]
+    fn strict_decryption_rejects_embedded_nul() {
+        let encrypted = encrypt_data("{\"name\":\"a\u{0}b\"}".to_string(), "hunter2".to_string());
+        assert!(encrypted.success);
+
+        // Only the strict path objects
+        assert!(decrypt_data(encrypted.data.clone(), "hunter2".to_string()).success);
+        let strict = decrypt_data_strict(encrypted.data, "hunter2".to_string());
+        assert!(!strict.success);
+        assert!(strict.data.is_empty());
+        assert_eq!(strict.error_code.as_deref(), Some("SUSPICIOUS_PLAINTEXT"));
+    }
+
+    #[test]
+    fn strict_decryption_allows_ordinary_whitespace() {
+        let text = "line one\n\tline two\r\n";
+        let encrypted = encrypt_data(text.to_string(), "hunter2".to_string());
+        let strict = decrypt_data_strict(encrypted.data, "hunter2".to_string());
+        assert!(strict.success);
+        assert_eq!(strict.data, text);
+
+        assert!(["\u{1b}", "\u{7f}", "\u{85}"].iter().all(|c| c.chars().all(is_unexpected_control)));
+    }
+
+    #[test]
+    fn dynamic_truncation_matches_rfc4226_example() {
+        // RFC 4226 section 5.4: offset 0xa selects 50ef7f19
+        let digest = HEXLOWER.decode(b"1f8698690e02ca16618550ef7f19da8e945b555a").unwrap();
+        assert_eq!(dynamic_truncate(&digest), 0x50ef7f19);
+        assert_eq!(format_code(dynamic_truncate(&digest), 6), "872921");
+    }
+
+    #[test]
+    fn dynamic_truncation_matches_rfc4226_intermediate_values() {
+        // RFC 4226 Appendix D: HMAC-SHA1 digest and truncated value for counters 0..=9
+        let vectors: [(&str, u32); 10] = [
+            ("cc93cf18508d94934c64b65d8ba7667fb7cde4b0", 0x4c93cf18),
+            ("75a48a19d4cbe100644e8ac1397eea747a2d33ab", 0x41397eea),
+            ("0bacb7fa082fef30782211938bc1c5e70416ff44", 0x082fef30),
+            ("66c28227d03a2d5529262ff016a1e6ef76557ece", 0x66ef7655),
+            ("a904c900a64b35909874b33e61c5938a8e15ed1c", 0x61c5938a),
+            ("a37e783d7b7233c083d4f62926c7a25f238d0316", 0x33c083d4),
+            ("bc9cd28561042c83f219324d3c607256c03272ae", 0x7256c032),
+            ("a4fb960c0bc06e1eabb804e5b397cdc4b45596fa", 0x04e5b397),
+            ("1b3c89f65e6c9e883012052823443f048b4332db", 0x2823443f),
+            ("1637409809a679dc698207310c8c7fc07290d9e5", 0x2679dc69),
+        ];
+        for (counter, (hmac, truncated)) in vectors.iter().enumerate() {
+            let message = (counter as u64).to_be_bytes();
+            let digest = hmac_digest(b"12345678901234567890", &message, HashAlgorithm::Sha1).unwrap();
+            assert_eq!(HEXLOWER.encode(&digest), *hmac);
+            assert_eq!(dynamic_truncate(&digest), *truncated);
+            assert_eq!(format_code(*truncated, 6), HOTP_CODES[counter]);
         }
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn dynamic_truncation_uses_the_last_byte_of_longer_digests() {
+        for length in [32, 64] {
+            let mut digest: Vec<u8> = (0..length as u8).collect();
+            // Offset 15, the furthest one, with the top bit of the window set
+            digest[length - 1] = 0xff;
+            digest[15] = 0x80 | 0x12;
+            assert_eq!(dynamic_truncate(&digest), 0x12101112);
+        }
+    }
+
+    #[test]
+    fn challenge_response_matches_known_digests() {
+        // RFC 2202 / RFC 4231 test case 2
+        let secret = SecureSecret::from_bytes(b"Jefe".to_vec());
+        let challenge = b"what do ya want for nothing?";
+        let vectors = [
+            ("SHA1", "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"),
+            ("SHA256", "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            (
+                "SHA512",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ];
+        for (algorithm, expected) in vectors {
+            let digest = challenge_response(&secret, challenge, algorithm).unwrap();
+            assert_eq!(HEXLOWER.encode(&digest), expected, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn challenge_response_rejects_bad_input() {
+        assert!(challenge_response(&rfc_secret(), b"challenge", "MD5").is_err());
+        let mut cleared = rfc_secret();
+        cleared.clear();
+        assert!(challenge_response(&cleared, b"challenge", "SHA1").is_err());
+    }
+
+    #[test]
+    fn every_constructor_rejects_empty_secrets() {
+        let empty = |result: napi::Result<SecureSecret>| result.err().map(|error| error.reason);
+        let expected = Some("Secret is empty".to_string());
+        assert_eq!(empty(SecureSecret::new(String::new())), expected);
+        assert_eq!(empty(SecureSecret::new("====".to_string())), expected);
+        assert_eq!(empty(SecureSecret::from_hex(String::new())), expected);
+        assert_eq!(empty(SecureSecret::from_hex(" \u{feff} ".to_string())), expected);
+        assert_eq!(empty(SecureSecret::from_base64(String::new())), expected);
+        assert_eq!(empty(SecureSecret::from_base64("==".to_string())), expected);
+    }
+
+    #[test]
+    fn verify_and_report_counts_down_the_matched_step() {
+        let secret = rfc_secret();
+        let report = |code: &str, window: u32, time: i64| {
+            verify_and_report(&secret, code.to_string(), eight_digits(), window, Some(time)).unwrap()
+        };
+
+        // "94287082" is the code of step 1 (30..60)
+        let exact = report("94287082", 0, 59);
+        assert!(exact.valid);
+        assert_eq!((exact.offset, exact.remaining_seconds), (Some(0), Some(1)));
+        assert_eq!(report("94287082", 0, 35).remaining_seconds, Some(25));
+        // A window keeps the code accepted for `window` more steps
+        assert_eq!(report("94287082", 1, 35).remaining_seconds, Some(55));
+        // The previous step's code is only good until its window closes
+        let previous = report("84755224", 1, 35);
+        assert_eq!((previous.offset, previous.remaining_seconds), (Some(-1), Some(25)));
+    }
+
+    #[test]
+    fn verify_and_report_invalid_code_has_no_countdown() {
+        let report = verify_and_report(&rfc_secret(), "84755224".to_string(), eight_digits(), 0, Some(59)).unwrap();
+        assert!(!report.valid);
+        assert_eq!((report.offset, report.remaining_seconds), (None, None));
+    }
+
+    fn verify_request_for(secret: &str, code: &str) -> VerifyRequest {
+        VerifyRequest {
+            secret: secret.to_string(),
+            code: code.to_string(),
+            params: None,
+            window: None,
+        }
+    }
+
+    #[test]
+    fn batch_verification_is_per_entry() {
+        // Base32 of the RFC secret; step 1 code "287082", step 0 "755224"
+        let rfc = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        let mut narrow = verify_request_for(rfc, "755224");
+        narrow.window = Some(0);
+        let mut bad_params = verify_request_for(rfc, "287082");
+        bad_params.params = Some(TotpParams {
+            digits: Some(3),
+            period: None,
+            algorithm: None,
+            t0: None,
+        });
+
+        let requests = vec![
+            verify_request_for(rfc, "287082"),
+            verify_request_for(rfc, "755224"),
+            narrow,
+            verify_request_for(rfc, "000000"),
+            verify_request_for("not base32!", "287082"),
+            verify_request_for("", "287082"),
+            bad_params,
+        ];
+        assert_eq!(
+            verify_totp_batch(requests, Some(59)),
+            vec![true, true, false, false, false, false, false]
+        );
+        assert!(verify_totp_batch(Vec::new(), Some(59)).is_empty());
+    }
+
+    #[test]
+    fn distinct_secrets_collide_only_at_their_shared_step() {
+        let rfc = rfc_secret();
+        let other = SecureSecret::new("JBSWY3DPEHPK3PXP".to_string()).unwrap();
+        // Both show 671072 in step 57065184
+        let shared_step = 57_065_184 * 30;
+        let collide = |window: u32, time: i64| secrets_collide_now(&rfc, &other, None, window, Some(time)).unwrap();
+
+        assert!(collide(0, shared_step));
+        assert!(collide(0, shared_step + 29));
+        assert!(!collide(0, shared_step + 30));
+        assert!(!collide(0, shared_step - 1));
+        // Within the window of a neighbouring step
+        assert!(collide(2, shared_step + 60));
+        assert!(!collide(1, shared_step + 60));
+    }
+
+    #[test]
+    fn a_secret_always_collides_with_itself() {
+        let rfc = rfc_secret();
+        let copy = rfc_secret();
+        for time in [0, 59, 1_111_111_109, 2_000_000_000] {
+            assert!(secrets_collide_now(&rfc, &copy, eight_digits(), 0, Some(time)).unwrap());
+        }
+    }
+
+    #[test]
+    fn copy_format_keeps_leading_zeros() {
+        assert_eq!(copy_format("00123456".to_string()).unwrap(), "00123456");
+        assert_eq!(copy_format("012 345".to_string()).unwrap(), "012345");
+        assert_eq!(copy_format("0012-3456".to_string()).unwrap(), "00123456");
+        // Steam codes are alphanumeric
+        assert_eq!(copy_format("2KM2P".to_string()).unwrap(), "2KM2P");
+        assert!(copy_format(" - ".to_string()).is_err());
+        assert!(copy_format("0012\u{200b}3456".to_string()).is_err());
+    }
+
+    #[test]
+    fn pasted_code_intact_detects_lost_zeros() {
+        assert!(pasted_code_intact("00123456".to_string(), 8));
+        assert!(pasted_code_intact(" 00123456\n".to_string(), 8));
+        assert!(!pasted_code_intact("123456".to_string(), 8));
+        assert!(!pasted_code_intact("1.23456E5".to_string(), 6));
+        assert!(!pasted_code_intact("001234567".to_string(), 8));
+    }
+
+    #[test]
+    fn no_replay_accepts_a_fresh_step_and_returns_its_counter() {
+        // At t=59 the 30-second counter is 1
+        let first = verify_totp_no_replay(&rfc_secret(), HOTP_CODES[1].to_string(), None, None, 1, Some(59)).unwrap();
+        assert!(first.valid && !first.replayed);
+        assert_eq!((first.matched_offset, first.counter), (Some(0), Some(1)));
+
+        let ahead =
+            verify_totp_no_replay(&rfc_secret(), HOTP_CODES[2].to_string(), Some(1), None, 1, Some(59)).unwrap();
+        assert!(ahead.valid);
+        assert_eq!((ahead.matched_offset, ahead.counter), (Some(1), Some(2)));
+    }
+
+    #[test]
+    fn no_replay_refuses_the_same_and_older_steps() {
+        let verify = |code: &str, last: i64| {
+            verify_totp_no_replay(&rfc_secret(), code.to_string(), Some(last), None, 1, Some(59)).unwrap()
+        };
+
+        let again = verify(HOTP_CODES[1], 1);
+        assert!(!again.valid && again.replayed);
+        assert_eq!((again.matched_offset, again.counter), (None, None));
+
+        let older = verify(HOTP_CODES[0], 1);
+        assert!(!older.valid && older.replayed);
+
+        // A code from the next window is refused once a later step was accepted
+        assert!(verify(HOTP_CODES[2], 2).replayed);
+    }
+
+    #[test]
+    fn no_replay_does_not_flag_wrong_or_out_of_window_codes() {
+        let wrong = verify_totp_no_replay(&rfc_secret(), "000000".to_string(), Some(1), None, 1, Some(59)).unwrap();
+        assert!(!wrong.valid && !wrong.replayed);
+
+        // With no window the previous step is simply not checked
+        let outside =
+            verify_totp_no_replay(&rfc_secret(), HOTP_CODES[0].to_string(), Some(5), None, 0, Some(59)).unwrap();
+        assert!(!outside.valid && !outside.replayed);
+
+        // Steps before zero are skipped rather than wrapping around
+        let early = verify_totp_no_replay(&rfc_secret(), HOTP_CODES[0].to_string(), None, None, 3, Some(10)).unwrap();
+        assert_eq!((early.matched_offset, early.counter), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn codes_until_counts_window_boundaries() {
+        // 59 -> 60 crosses one 30-second boundary, 0 -> 59 crosses one, 0 -> 60 crosses two
+        assert_eq!(codes_until(30, 59, 60).unwrap(), 1);
+        assert_eq!(codes_until(30, 0, 59).unwrap(), 1);
+        assert_eq!(codes_until(30, 0, 60).unwrap(), 2);
+        assert_eq!(codes_until(60, 0, 59).unwrap(), 0);
+        assert_eq!(codes_until(60, 30, 3_630).unwrap(), 60);
+        assert_eq!(codes_until(1, 100, 105).unwrap(), 5);
+    }
+
+    #[test]
+    fn codes_until_equal_times_is_zero() {
+        assert_eq!(codes_until(30, 1_700_000_000, 1_700_000_000).unwrap(), 0);
+        assert_eq!(codes_until(30, 60, 89).unwrap(), 0);
+    }
+
+    #[test]
+    fn codes_until_rejects_reversed_times_and_zero_period() {
+        assert!(codes_until(30, 61, 60).is_err());
+        assert!(codes_until(0, 0, 60).is_err());
+        assert!(codes_until(0, 60, 60).is_err());
+        // Times before the Unix epoch have no time step
+        assert!(codes_until(30, -31, -1).is_err());
+    }
+
+    #[test]
+    fn secret_case_does_not_change_codes() {
+        let lower = SecureSecret::new("jbswy3dpehpk3pxp".to_string()).unwrap();
+        let upper = SecureSecret::new("JBSWY3DPEHPK3PXP".to_string()).unwrap();
+        for time in [0, 59, 1_111_111_109, 2_000_000_000] {
+            assert_eq!(
+                generate_totp(&lower, Some(time)).unwrap(),
+                generate_totp(&upper, Some(time)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn every_constructor_strips_decode_artifacts() {
+        let base32 = SecureSecret::new("\u{feff}JBSWY3DP\u{200b}EHPK3PXP\0".to_string()).unwrap();
+        let hex = SecureSecret::from_hex("\u{feff}48656c6c6f21\u{200b}deadbeef\r\n\0".to_string()).unwrap();
+        let base64 = SecureSecret::from_base64("\u{feff}SGVsbG8h\u{200b}3q2+7w==\0".to_string()).unwrap();
+        assert_eq!(base32.bytes().unwrap(), b"Hello!\xde\xad\xbe\xef");
+        assert_eq!(hex.bytes().unwrap(), base32.bytes().unwrap());
+        assert_eq!(base64.bytes().unwrap(), base32.bytes().unwrap());
+    }
+}
@@ -0,0 +1,350 @@
+// OCRA challenge-response one-time passwords (RFC 6287)
+//
+// A suite string such as "OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1" names the hash,
+// the number of digits and which inputs go into the HMAC. The message is the
+// suite itself, a zero byte, then in order: counter (8 bytes), challenge
+// (padded to 128 bytes), PIN hash, session information and time step (8 bytes).
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+use napi_derive::napi;
+use zeroize::Zeroize;
+
+use crate::{dynamic_truncate, hmac_digest, HashAlgorithm, SecureSecret};
+
+const CHALLENGE_LENGTH: usize = 128;
+const DEFAULT_SESSION_LENGTH: usize = 64;
+
+// Compute an OCRA response for `suite`
+// `counter` is required by suites with "C", `timestamp` (Unix seconds) by suites
+// with "T", `session` (hex) by suites with "S" and `pin_hash` (hex digest of the
+// PIN) by suites with "P"; inputs the suite does not use are ignored
+#[napi]
+pub fn generate_ocra(
+    secret: &SecureSecret,
+    suite: String,
+    challenge: String,
+    counter: Option<i64>,
+    timestamp: Option<i64>,
+    session: Option<String>,
+    pin_hash: Option<String>,
+) -> napi::Result<String> {
+    let parsed = OcraSuite::parse(&suite)?;
+
+    let mut message = Vec::with_capacity(suite.len() + 1 + 8 + CHALLENGE_LENGTH + 64 + 8);
+    message.extend_from_slice(suite.as_bytes());
+    message.push(0);
+
+    if parsed.counter {
+        let counter = counter.ok_or_else(|| napi::Error::from_reason("Suite requires a counter"))?;
+        message.extend_from_slice(&counter.to_be_bytes());
+    }
+
+    message.extend_from_slice(&encode_challenge(&challenge, parsed.challenge_format)?);
+
+    if let Some(algorithm) = parsed.pin {
+        let pin_hash = pin_hash.ok_or_else(|| napi::Error::from_reason("Suite requires a PIN hash"))?;
+        let mut hash = decode_hex(&pin_hash, "PIN hash")?;
+        if hash.len() != algorithm.digest_len() {
+            hash.zeroize();
+            return Err(napi::Error::from_reason(format!(
+                "Invalid PIN hash: expected a {} digest",
+                algorithm.name()
+            )));
+        }
+        message.extend_from_slice(&hash);
+        hash.zeroize();
+    }
+
+    if let Some(length) = parsed.session {
+        let session = session.ok_or_else(|| napi::Error::from_reason("Suite requires session information"))?;
+        let session = decode_hex(&session, "session information")?;
+        if session.len() > length {
+            return Err(napi::Error::from_reason(format!(
+                "Session information is longer than {} bytes",
+                length
+            )));
+        }
+        // Left-padded with zeros to the declared length
+        message.resize(message.len() + length - session.len(), 0);
+        message.extend_from_slice(&session);
+    }
+
+    if let Some(step) = parsed.time_step {
+        let timestamp = timestamp.ok_or_else(|| napi::Error::from_reason("Suite requires a timestamp"))?;
+        if timestamp < 0 {
+            return Err(napi::Error::from_reason("Invalid timestamp: must not be negative"));
+        }
+        message.extend_from_slice(&(timestamp / step).to_be_bytes());
+    }
+
+    let mut digest = hmac_digest(secret.bytes()?, &message, parsed.algorithm)?;
+    let value = dynamic_truncate(&digest);
+    digest.zeroize();
+    message.zeroize();
+
+    let code = value as u64 % 10u64.pow(parsed.digits);
+    Ok(format!("{:0>width$}", code, width = parsed.digits as usize))
+}
+
+#[derive(Clone, Copy)]
+enum ChallengeFormat {
+    Alphanumeric,
+    Numeric,
+    Hex,
+}
+
+struct OcraSuite {
+    algorithm: HashAlgorithm,
+    digits: u32,
+    counter: bool,
+    challenge_format: ChallengeFormat,
+    pin: Option<HashAlgorithm>,
+    session: Option<usize>,
+    // Seconds per time step
+    time_step: Option<i64>,
+}
+
+impl OcraSuite {
+    fn parse(suite: &str) -> napi::Result<Self> {
+        let invalid = |detail: &str| napi::Error::from_reason(format!("Invalid OCRA suite {}: {}", suite, detail));
+
+        let mut parts = suite.split(':');
+        let (version, crypto, data_input) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(version), Some(crypto), Some(data_input), None) => (version, crypto, data_input),
+            _ => return Err(invalid("expected OCRA-1:<function>:<inputs>")),
+        };
+        if version != "OCRA-1" {
+            return Err(invalid("only OCRA-1 is supported"));
+        }
+
+        // CryptoFunction: HOTP-<hash>-<digits>
+        let crypto_parts: Vec<&str> = crypto.split('-').collect();
+        let (algorithm, digits) = match crypto_parts.as_slice() {
+            ["HOTP", hash, digits] => (
+                HashAlgorithm::from_name(hash).map_err(|_| invalid("unsupported hash"))?,
+                digits.parse::<u32>().map_err(|_| invalid("invalid digit count"))?,
+            ),
+            _ => return Err(invalid("expected HOTP-<hash>-<digits>")),
+        };
+        // 0 (untruncated output) is allowed by the RFC but not a usable code
+        if !(4..=10).contains(&digits) {
+            return Err(invalid("digits must be between 4 and 10"));
+        }
+
+        let mut parsed = Self {
+            algorithm,
+            digits,
+            counter: false,
+            challenge_format: ChallengeFormat::Numeric,
+            pin: None,
+            session: None,
+            time_step: None,
+        };
+
+        let mut inputs = data_input.split('-').peekable();
+        if inputs.peek() == Some(&"C") {
+            parsed.counter = true;
+            inputs.next();
+        }
+
+        let question = inputs.next().ok_or_else(|| invalid("missing challenge"))?;
+        let (format, length) = match (question.get(..2), question.get(2..)) {
+            (Some("QA"), Some(length)) => (ChallengeFormat::Alphanumeric, length),
+            (Some("QN"), Some(length)) => (ChallengeFormat::Numeric, length),
+            (Some("QH"), Some(length)) => (ChallengeFormat::Hex, length),
+            _ => return Err(invalid("challenge must be QA, QN or QH")),
+        };
+        parsed.challenge_format = format;
+        if !length.parse::<usize>().is_ok_and(|length| (4..=64).contains(&length)) {
+            return Err(invalid("challenge length must be between 04 and 64"));
+        }
+
+        for input in inputs {
+            match input.as_bytes().first() {
+                Some(b'P') if parsed.pin.is_none() && parsed.session.is_none() && parsed.time_step.is_none() => {
+                    parsed.pin = Some(HashAlgorithm::from_name(&input[1..]).map_err(|_| invalid("unsupported PIN hash"))?);
+                }
+                Some(b'S') if parsed.session.is_none() && parsed.time_step.is_none() => {
+                    parsed.session = Some(match &input[1..] {
+                        "" => DEFAULT_SESSION_LENGTH,
+                        length => length.parse().map_err(|_| invalid("invalid session length"))?,
+                    });
+                }
+                Some(b'T') if parsed.time_step.is_none() => {
+                    parsed.time_step = Some(parse_time_step(&input[1..]).ok_or_else(|| invalid("invalid time step"))?);
+                }
+                _ => return Err(invalid(&format!("unexpected input {}", input))),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+// Time step such as "30S", "1M" or "1H", in seconds
+fn parse_time_step(step: &str) -> Option<i64> {
+    let (amount, unit) = step.split_at(step.len().checked_sub(1)?);
+    let amount: i64 = amount.parse().ok()?;
+    let (max, seconds) = match unit {
+        "S" => (59, 1),
+        "M" => (59, 60),
+        "H" => (48, 3600),
+        _ => return None,
+    };
+    (1..=max).contains(&amount).then_some(amount * seconds)
+}
+
+// Challenge bytes, right-padded with zeros to 128 bytes
+// The suite's declared length is not enforced as a maximum: the RFC's own
+// mutual challenge-response vectors send 16 characters under "QA08"
+fn encode_challenge(challenge: &str, format: ChallengeFormat) -> napi::Result<Vec<u8>> {
+    if challenge.len() < 4 {
+        return Err(napi::Error::from_reason("Challenge must be at least 4 characters"));
+    }
+
+    let mut bytes = match format {
+        ChallengeFormat::Alphanumeric => {
+            if !challenge.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(napi::Error::from_reason("Challenge must be alphanumeric"));
+            }
+            challenge.as_bytes().to_vec()
+        }
+        ChallengeFormat::Numeric => {
+            if !challenge.chars().all(|c| c.is_ascii_digit()) {
+                return Err(napi::Error::from_reason("Challenge must be numeric"));
+            }
+            hex_digits_to_bytes(&decimal_to_hex_digits(challenge))
+        }
+        ChallengeFormat::Hex => {
+            if !challenge.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(napi::Error::from_reason("Challenge must be hexadecimal"));
+            }
+            let digits: Vec<u8> = challenge.chars().filter_map(|c| c.to_digit(16)).map(|d| d as u8).collect();
+            hex_digits_to_bytes(&digits)
+        }
+    };
+    if bytes.len() > CHALLENGE_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Challenge does not fit in {} bytes",
+            CHALLENGE_LENGTH
+        )));
+    }
+    bytes.resize(CHALLENGE_LENGTH, 0);
+    Ok(bytes)
+}
+
+// Hex digits of a decimal number, most significant first (by repeated division)
+fn decimal_to_hex_digits(decimal: &str) -> Vec<u8> {
+    let mut number: Vec<u8> = decimal.bytes().map(|b| b - b'0').collect();
+    let mut hex = Vec::new();
+    while number.iter().any(|&digit| digit != 0) {
+        let mut remainder = 0u32;
+        for digit in number.iter_mut() {
+            let value = remainder * 10 + *digit as u32;
+            *digit = (value / 16) as u8;
+            remainder = value % 16;
+        }
+        hex.push(remainder as u8);
+    }
+    if hex.is_empty() {
+        hex.push(0);
+    }
+    hex.reverse();
+    hex
+}
+
+// Pack hex digits into bytes; an odd trailing digit becomes the high nibble,
+// matching the RFC's right-padding of the hex string with '0'
+fn hex_digits_to_bytes(digits: &[u8]) -> Vec<u8> {
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn decode_hex(hex: &str, what: &str) -> napi::Result<Vec<u8>> {
+    HEXLOWER_PERMISSIVE
+        .decode(hex.trim().as_bytes())
+        .map_err(|_| napi::Error::from_reason(format!("Invalid {}: expected hex", what)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6287 appendix C seeds
+    const SEED20: &[u8] = b"12345678901234567890";
+    const SEED32: &[u8] = b"12345678901234567890123456789012";
+    const SEED64: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+    // SHA-1 of the PIN "1234"
+    const PIN_HASH: &str = "7110eda4d09e062aa5e4a390b0a572ac0d2c0220";
+
+    fn ocra(seed: &[u8], suite: &str, challenge: &str, counter: Option<i64>, timestamp: Option<i64>) -> String {
+        let secret = SecureSecret::from_bytes(seed.to_vec());
+        let pin_hash = suite.contains("-PSHA1").then(|| PIN_HASH.to_string());
+        generate_ocra(&secret, suite.to_string(), challenge.to_string(), counter, timestamp, None, pin_hash).unwrap()
+    }
+
+    #[test]
+    fn one_way_challenge_response_vectors() {
+        let suite = "OCRA-1:HOTP-SHA1-6:QN08";
+        assert_eq!(ocra(SEED20, suite, "00000000", None, None), "237653");
+        assert_eq!(ocra(SEED20, suite, "55555555", None, None), "388898");
+        assert_eq!(ocra(SEED20, suite, "99999999", None, None), "294470");
+
+        let suite = "OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1";
+        assert_eq!(ocra(SEED32, suite, "12345678", Some(0), None), "65347737");
+        assert_eq!(ocra(SEED32, suite, "12345678", Some(9), None), "08522129");
+
+        let suite = "OCRA-1:HOTP-SHA256-8:QN08-PSHA1";
+        assert_eq!(ocra(SEED32, suite, "00000000", None, None), "83238735");
+        assert_eq!(ocra(SEED32, suite, "44444444", None, None), "86807031");
+
+        let suite = "OCRA-1:HOTP-SHA512-8:C-QN08";
+        assert_eq!(ocra(SEED64, suite, "00000000", Some(0), None), "07016083");
+        assert_eq!(ocra(SEED64, suite, "99999999", Some(9), None), "31409299");
+    }
+
+    #[test]
+    fn time_based_vectors() {
+        // Time step 0x132d0b6 minutes
+        let timestamp = Some(0x132d0b6 * 60);
+        let suite = "OCRA-1:HOTP-SHA512-8:QN08-T1M";
+        assert_eq!(ocra(SEED64, suite, "00000000", None, timestamp), "95209754");
+        assert_eq!(ocra(SEED64, suite, "44444444", None, timestamp), "36209546");
+        // Anywhere inside the same minute gives the same response
+        assert_eq!(ocra(SEED64, suite, "00000000", None, timestamp.map(|t| t + 59)), "95209754");
+    }
+
+    #[test]
+    fn mutual_challenge_response_vectors() {
+        assert_eq!(ocra(SEED32, "OCRA-1:HOTP-SHA256-8:QA08", "CLI22220SRV11110", None, None), "28247970");
+        assert_eq!(ocra(SEED64, "OCRA-1:HOTP-SHA512-8:QA08-PSHA1", "SRV11110CLI22220", None, None), "18806276");
+    }
+
+    #[test]
+    fn signature_vector() {
+        assert_eq!(ocra(SEED32, "OCRA-1:HOTP-SHA256-8:QA08", "SIG13000", None, None), "76028668");
+    }
+
+    #[test]
+    fn missing_suite_inputs_are_rejected() {
+        let secret = SecureSecret::from_bytes(SEED32.to_vec());
+        let generate = |suite: &str, counter: Option<i64>, pin_hash: Option<&str>| {
+            generate_ocra(
+                &secret,
+                suite.to_string(),
+                "12345678".to_string(),
+                counter,
+                None,
+                None,
+                pin_hash.map(str::to_string),
+            )
+        };
+        assert!(generate("OCRA-1:HOTP-SHA256-8:C-QN08", None, None).is_err());
+        assert!(generate("OCRA-1:HOTP-SHA256-8:QN08-PSHA1", None, None).is_err());
+        // PIN hash of the wrong length
+        assert!(generate("OCRA-1:HOTP-SHA256-8:QN08-PSHA1", None, Some("7110eda4")).is_err());
+        assert!(generate("OCRA-1:HOTP-SHA256-8:QN08-T1M", None, None).is_err());
+    }
+}
@@ -0,0 +1,582 @@
+// otpauth:// Key URI parsing
+// https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+
+use napi_derive::napi;
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::{
+    current_timestamp, decode_base32, decrypt_bytes_bound, encrypt_bytes_bound, normalize_base32, strip_decode_artifacts,
+    HashAlgorithm, ResolvedParams, TotpParams, TOTP_DIGITS, TOTP_PERIOD,
+};
+
+const OTPAUTH_SCHEME: &str = "otpauth://";
+const PROTECTED_QR_PREFIX: &str = "truefa://protected?v=1&data=";
+// Bound into the AAD so a protected QR cannot be passed off as another blob type
+const PROTECTED_QR_CONTEXT: &[u8] = b"truefa-protected-qr-v1";
+const MIN_PIN_LENGTH: usize = 4;
+
+// Query parameters `parse_otpauth_uri` understands; anything else is ignored
+pub(crate) const SUPPORTED_PARAMETERS: [&str; 6] = ["secret", "issuer", "algorithm", "digits", "period", "counter"];
+
+// Issuer and account name taken from an otpauth label
+#[napi(object)]
+pub struct OtpauthLabel {
+    pub issuer: Option<String>,
+    pub account: String,
+}
+
+// Fields of a parsed otpauth URI, with defaults applied
+#[napi(object)]
+pub struct OtpauthUri {
+    // "totp" or "hotp"
+    pub otp_type: String,
+    pub issuer: Option<String>,
+    // The label prefix and the issuer parameter name different issuers
+    pub issuer_mismatch: bool,
+    pub account: String,
+    // Base32 secret, normalized to uppercase without spaces
+    pub secret: String,
+    pub algorithm: String,
+    pub digits: u32,
+    pub period: u32,
+    // Initial counter, present for HOTP only
+    pub counter: Option<i64>,
+}
+
+// Split an otpauth label into issuer and account name
+// Handles a literal or percent-encoded colon ("Issuer:Account", "Issuer%3AAccount"),
+// labels without an issuer, a leading slash and the optional spaces the spec
+// allows around the separator
+#[napi]
+pub fn parse_otpauth_label(label: String) -> napi::Result<OtpauthLabel> {
+    let raw = label.trim().trim_start_matches('/');
+
+    // A literal colon is the separator, so an issuer may itself contain an encoded
+    // one ("ACME%3A%20Corp:alice"); otherwise fall back to the first decoded colon
+    let (issuer, account) = match raw.split_once(':') {
+        Some((issuer, account)) => (Some(percent_decode(issuer, false)?), percent_decode(account, false)?),
+        None => {
+            let decoded = percent_decode(raw, false)?;
+            match decoded.split_once(':') {
+                Some((issuer, account)) => (Some(issuer.to_string()), account.to_string()),
+                None => (None, decoded),
+            }
+        }
+    };
+
+    let account = account.trim();
+    if account.is_empty() {
+        return Err(napi::Error::from_reason("Label has no account name"));
+    }
+
+    Ok(OtpauthLabel {
+        issuer: issuer
+            .map(|issuer| issuer.trim().to_string())
+            .filter(|issuer| !issuer.is_empty()),
+        account: account.to_string(),
+    })
+}
+
+// Parse an otpauth://totp/... or otpauth://hotp/... URI
+#[napi]
+pub fn parse_otpauth_uri(uri: String) -> napi::Result<OtpauthUri> {
+    let uri = strip_decode_artifacts(&uri);
+    let uri = uri.trim();
+    let rest = uri
+        .get(..OTPAUTH_SCHEME.len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case(OTPAUTH_SCHEME))
+        .map(|_| &uri[OTPAUTH_SCHEME.len()..])
+        .ok_or_else(|| napi::Error::from_reason("Not an otpauth:// URI"))?;
+
+    let (otp_type, rest) = rest
+        .split_once('/')
+        .ok_or_else(|| napi::Error::from_reason("otpauth URI has no label"))?;
+    let otp_type = otp_type.to_lowercase();
+    if otp_type != "totp" && otp_type != "hotp" {
+        return Err(napi::Error::from_reason(format!("Unsupported OTP type: {}", otp_type)));
+    }
+
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let label = parse_otpauth_label(label.to_string())?;
+
+    let mut secret = None;
+    let mut issuer = None;
+    let mut algorithm = None;
+    let mut digits = None;
+    let mut period = None;
+    let mut counter = None;
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value, true)?;
+        match key.to_lowercase().as_str() {
+            "secret" => secret = Some(value),
+            "issuer" => issuer = Some(value),
+            "algorithm" => algorithm = Some(value),
+            "digits" => digits = Some(parse_number::<u32>("digits", &value)?),
+            "period" => period = Some(parse_number::<u32>("period", &value)?),
+            "counter" => counter = Some(parse_counter(&value)?),
+            // Unknown parameters (e.g. "image") are ignored per the spec
+            _ => {}
+        }
+    }
+
+    let mut secret = secret
+        .map(|secret| normalize_base32(&secret))
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| napi::Error::from_reason("otpauth URI has no secret"))?;
+    match decode_base32(&secret) {
+        Some(mut bytes) => bytes.zeroize(),
+        None => {
+            secret.zeroize();
+            return Err(napi::Error::from_reason("Invalid Base32 encoding"));
+        }
+    }
+
+    let is_hotp = otp_type == "hotp";
+    if is_hotp && counter.is_none() {
+        return Err(napi::Error::from_reason("HOTP URI is missing the counter parameter"));
+    }
+
+    let params = ResolvedParams::resolve(Some(TotpParams {
+        digits,
+        period,
+        algorithm,
+        t0: None,
+    }))?;
+
+    // The issuer parameter takes precedence over the label prefix. Both are fully
+    // percent-decoded at this point, so "Google%20Inc" and "Google Inc" compare equal
+    let issuer = issuer
+        .map(|issuer| issuer.trim().to_string())
+        .filter(|issuer| !issuer.is_empty());
+    let issuer_mismatch = matches!((&issuer, &label.issuer), (Some(param), Some(prefix)) if param != prefix);
+    let issuer = issuer.or(label.issuer);
+
+    Ok(OtpauthUri {
+        otp_type,
+        issuer,
+        issuer_mismatch,
+        account: label.account,
+        secret,
+        algorithm: params.algorithm.name().to_string(),
+        digits: params.digits,
+        period: params.period,
+        counter: if is_hotp { counter } else { None },
+    })
+}
+
+// Build an otpauth URI for an account, the inverse of `parse_otpauth_uri`
+// Parameters at their defaults are left out to keep QR codes small
+#[napi]
+pub fn build_otpauth_uri(account: Account) -> napi::Result<String> {
+    let otp_type = account.otp_type.as_deref().unwrap_or("totp").to_lowercase();
+    if otp_type != "totp" && otp_type != "hotp" {
+        return Err(napi::Error::from_reason(format!(
+            "{} accounts cannot be exported as an otpauth URI",
+            otp_type
+        )));
+    }
+
+    let mut secret = normalize_base32(&account.secret).trim_end_matches('=').to_string();
+    match decode_base32(&secret) {
+        Some(mut bytes) => bytes.zeroize(),
+        None => {
+            secret.zeroize();
+            return Err(napi::Error::from_reason("Invalid Base32 encoding"));
+        }
+    }
+    let params = ResolvedParams::resolve(Some(account.params()))?;
+
+    let issuer = account.issuer.trim();
+    let label = if issuer.is_empty() {
+        percent_encode(&account.name)
+    } else {
+        format!("{}:{}", percent_encode(issuer), percent_encode(&account.name))
+    };
+
+    let mut uri = format!("{}{}/{}?secret={}", OTPAUTH_SCHEME, otp_type, label, secret);
+    secret.zeroize();
+    if !issuer.is_empty() {
+        uri.push_str(&format!("&issuer={}", percent_encode(issuer)));
+    }
+    if !matches!(params.algorithm, HashAlgorithm::Sha1) {
+        uri.push_str(&format!("&algorithm={}", params.algorithm.name()));
+    }
+    if params.digits != TOTP_DIGITS as u32 {
+        uri.push_str(&format!("&digits={}", params.digits));
+    }
+    if otp_type == "hotp" {
+        let counter = account
+            .counter
+            .ok_or_else(|| napi::Error::from_reason("HOTP account is missing its counter"))?;
+        uri.push_str(&format!("&counter={}", counter));
+    } else if params.period != TOTP_PERIOD as u32 {
+        uri.push_str(&format!("&period={}", params.period));
+    }
+
+    Ok(uri)
+}
+
+// Build a truefa:// QR payload holding an account's otpauth URI encrypted under a PIN
+// Meant for showing a QR on a screen others can see: scanning it without the PIN
+// yields nothing. The PIN goes through the vault's full PBKDF2 rather than a
+// faster KDF, since a captured QR can be attacked offline and a short PIN gives
+// little margin; one derivation per transfer is affordable. Rate limiting PIN
+// entries in the importing app only slows guessing through its UI, so tell
+// users to pick a PIN of 6+ digits and to hide the QR once scanned.
+#[napi]
+pub fn build_protected_otpauth_qr(account: Account, mut pin: String) -> napi::Result<String> {
+    let result = validate_pin(&pin).and_then(|_| {
+        let mut uri = build_otpauth_uri(account)?;
+        let sealed = encrypt_bytes_bound(uri.as_bytes(), &pin, PROTECTED_QR_CONTEXT);
+        uri.zeroize();
+        sealed.map_err(napi::Error::from)
+    });
+    pin.zeroize();
+
+    let sealed = result?;
+    Ok(format!(
+        "{}{}",
+        PROTECTED_QR_PREFIX,
+        base64::encode_config(&sealed, base64::URL_SAFE_NO_PAD)
+    ))
+}
+
+// Decrypt a payload from `build_protected_otpauth_qr` into an account
+// `id` is left empty for the app to assign, as for a scanned otpauth QR, and
+// `created_at` is set to now
+#[napi]
+pub fn import_protected_qr(payload: String, mut pin: String) -> napi::Result<Account> {
+    let encoded = payload
+        .trim()
+        .strip_prefix(PROTECTED_QR_PREFIX)
+        .ok_or_else(|| napi::Error::from_reason("Not a protected TrueFA QR code"))?;
+    let sealed = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)));
+    let opened =
+        sealed.and_then(|sealed| decrypt_bytes_bound(&sealed, &pin, PROTECTED_QR_CONTEXT).map_err(napi::Error::from));
+    pin.zeroize();
+
+    let mut uri = String::from_utf8(opened?).map_err(|_| napi::Error::from_reason("Invalid protected QR contents"))?;
+    let parsed = parse_otpauth_uri(uri.clone());
+    uri.zeroize();
+    let parsed = parsed?;
+
+    let is_totp = parsed.otp_type == "totp";
+    Ok(Account {
+        name: parsed.account,
+        issuer: parsed.issuer.unwrap_or_default(),
+        secret: parsed.secret,
+        created_at: current_timestamp() * 1000,
+        algorithm: Some(parsed.algorithm),
+        digits: Some(parsed.digits),
+        period: if is_totp { Some(parsed.period) } else { None },
+        otp_type: Some(parsed.otp_type),
+        counter: parsed.counter,
+        ..Default::default()
+    })
+}
+
+fn validate_pin(pin: &str) -> napi::Result<()> {
+    if pin.chars().count() < MIN_PIN_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "PIN must be at least {} characters",
+            MIN_PIN_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+// Parse a numeric query parameter, naming the parameter and the bad value on error
+// so a user can tell which field of a scanned code is broken
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> napi::Result<T> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(napi::Error::from_reason(format!("Invalid {} parameter: value is empty", name)));
+    }
+    value.parse().map_err(|_| {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        let reason = if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            "number is out of range"
+        } else {
+            "expected a whole number"
+        };
+        napi::Error::from_reason(format!("Invalid {} parameter \"{}\": {}", name, value, reason))
+    })
+}
+
+// HOTP counters start at zero, as `validate_vault_integrity` also requires
+fn parse_counter(value: &str) -> napi::Result<i64> {
+    let counter = parse_number::<i64>("counter", value)?;
+    if counter < 0 {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid counter parameter \"{}\": must not be negative",
+            value.trim()
+        )));
+    }
+    Ok(counter)
+}
+
+// Decode %XX escapes (and '+' as space in query values) into UTF-8 text
+fn percent_decode(input: &str, plus_as_space: bool) -> napi::Result<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escape = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| napi::Error::from_reason("Invalid percent-encoding in otpauth URI"))?;
+                decoded.push(escape);
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| napi::Error::from_reason("otpauth URI is not valid UTF-8"))
+}
+
+// Percent-encode everything outside the RFC 3986 unreserved set
+fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(raw: &str) -> (Option<String>, String) {
+        let parsed = parse_otpauth_label(raw.to_string()).unwrap();
+        (parsed.issuer, parsed.account)
+    }
+
+    #[test]
+    fn label_with_encoded_colon() {
+        assert_eq!(label("Example%3Aalice"), (Some("Example".to_string()), "alice".to_string()));
+        // An encoded colon inside the issuer when a literal one separates
+        assert_eq!(label("ACME%3A%20Corp:alice"), (Some("ACME: Corp".to_string()), "alice".to_string()));
+    }
+
+    #[test]
+    fn label_without_issuer() {
+        assert_eq!(label("alice@example.com"), (None, "alice@example.com".to_string()));
+        assert_eq!(label("/alice"), (None, "alice".to_string()));
+        assert_eq!(label(":alice"), (None, "alice".to_string()));
+    }
+
+    #[test]
+    fn label_with_whitespace() {
+        assert_eq!(label("Example: alice"), (Some("Example".to_string()), "alice".to_string()));
+        assert_eq!(label("Example%3A%20alice"), (Some("Example".to_string()), "alice".to_string()));
+        assert_eq!(label("  Big%20Bank :  alice "), (Some("Big Bank".to_string()), "alice".to_string()));
+        assert!(parse_otpauth_label("Example: ".to_string()).is_err());
+    }
+
+    fn parse(uri: &str) -> OtpauthUri {
+        parse_otpauth_uri(uri.to_string()).unwrap()
+    }
+
+    #[test]
+    fn issuer_differing_only_in_encoding_is_not_flagged() {
+        let uri = parse("otpauth://totp/Google%20Inc:alice?secret=JBSWY3DPEHPK3PXP&issuer=Google+Inc");
+        assert!(!uri.issuer_mismatch);
+        assert_eq!(uri.issuer.as_deref(), Some("Google Inc"));
+
+        let uri = parse("otpauth://totp/Google Inc:alice?secret=JBSWY3DPEHPK3PXP&issuer=Google%20Inc");
+        assert!(!uri.issuer_mismatch);
+    }
+
+    #[test]
+    fn genuinely_different_issuer_is_flagged() {
+        let uri = parse("otpauth://totp/Google:alice?secret=JBSWY3DPEHPK3PXP&issuer=Evil%20Corp");
+        assert!(uri.issuer_mismatch);
+        // The parameter wins
+        assert_eq!(uri.issuer.as_deref(), Some("Evil Corp"));
+    }
+
+    #[test]
+    fn one_sided_issuer_is_not_flagged() {
+        assert!(!parse("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&issuer=Example").issuer_mismatch);
+        assert!(!parse("otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP").issuer_mismatch);
+    }
+
+    // Build a URI for the account, parse it back and check every field survived
+    fn assert_round_trip(
+        issuer: &str,
+        name: &str,
+        otp_type: &str,
+        algorithm: Option<&str>,
+        digits: u32,
+        period: u32,
+        counter: Option<i64>,
+    ) {
+        let account = Account {
+            name: name.to_string(),
+            issuer: issuer.to_string(),
+            secret: "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP".to_string(),
+            algorithm: algorithm.map(str::to_string),
+            digits: Some(digits),
+            period: Some(period),
+            otp_type: Some(otp_type.to_string()),
+            counter,
+            ..Default::default()
+        };
+        let uri = build_otpauth_uri(account.clone()).unwrap();
+        let parsed = parse_otpauth_uri(uri.clone()).unwrap();
+
+        assert_eq!(parsed.secret, account.secret, "{}", uri);
+        assert_eq!(parsed.issuer.as_deref(), Some(issuer).filter(|issuer| !issuer.is_empty()), "{}", uri);
+        assert!(!parsed.issuer_mismatch, "{}", uri);
+        assert_eq!(parsed.account, name, "{}", uri);
+        assert_eq!(parsed.otp_type, otp_type, "{}", uri);
+        assert_eq!(parsed.algorithm, algorithm.unwrap_or("SHA1"), "{}", uri);
+        assert_eq!(parsed.digits, digits, "{}", uri);
+        if otp_type == "totp" {
+            assert_eq!(parsed.period, period, "{}", uri);
+        }
+        assert_eq!(parsed.counter, counter, "{}", uri);
+    }
+
+    #[test]
+    fn round_trip_issuer_with_colon_and_space() {
+        assert_round_trip("ACME: Corp", "alice@example.com", "totp", Some("SHA1"), 6, 30, None);
+    }
+
+    #[test]
+    fn round_trip_unicode_issuer_and_non_default_parameters() {
+        assert_round_trip("Überbank 東京", "bob", "totp", Some("SHA512"), 8, 60, None);
+    }
+
+    #[test]
+    fn round_trip_without_issuer() {
+        assert_round_trip("", "carol+2fa@example.com", "totp", Some("SHA256"), 7, 15, None);
+    }
+
+    #[test]
+    fn round_trip_hotp_counter() {
+        assert_round_trip("Example", "dave", "hotp", None, 6, 30, Some(42));
+    }
+
+    fn protected_account() -> Account {
+        Account {
+            id: "local-id".to_string(),
+            name: "alice@example.com".to_string(),
+            issuer: "Example".to_string(),
+            secret: "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP".to_string(),
+            digits: Some(8),
+            period: Some(60),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn protected_qr_round_trip() {
+        let payload = build_protected_otpauth_qr(protected_account(), "482916".to_string()).unwrap();
+        assert!(payload.starts_with(PROTECTED_QR_PREFIX));
+        assert!(!payload.contains("JBSWY3DP"));
+
+        let imported = import_protected_qr(payload, "482916".to_string()).unwrap();
+        assert_eq!(imported.id, "");
+        assert_eq!(imported.name, "alice@example.com");
+        assert_eq!(imported.issuer, "Example");
+        assert_eq!(imported.secret, "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP");
+        assert_eq!((imported.digits, imported.period), (Some(8), Some(60)));
+    }
+
+    #[test]
+    fn protected_qr_needs_the_right_pin() {
+        let payload = build_protected_otpauth_qr(protected_account(), "482916".to_string()).unwrap();
+        assert!(import_protected_qr(payload.clone(), "482917".to_string()).is_err());
+
+        // The payload is bound to the QR format and does not open as a plain vault
+        let encoded = payload.strip_prefix(PROTECTED_QR_PREFIX).unwrap();
+        let sealed = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).unwrap();
+        assert!(crate::decrypt_bytes(&sealed, "482916").is_err());
+    }
+
+    #[test]
+    fn protected_qr_rejects_bad_input() {
+        assert!(build_protected_otpauth_qr(protected_account(), "123".to_string()).is_err());
+        let plain_uri = "otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP".to_string();
+        let not_ours = import_protected_qr(plain_uri, "1234".to_string());
+        assert_eq!(not_ours.unwrap_err().reason, "Not a protected TrueFA QR code");
+        assert!(import_protected_qr(format!("{}not*base64", PROTECTED_QR_PREFIX), "1234".to_string()).is_err());
+    }
+
+    fn parse_error(uri: &str) -> String {
+        parse_otpauth_uri(uri.to_string()).err().unwrap().reason
+    }
+
+    #[test]
+    fn non_numeric_parameters_are_named_in_the_error() {
+        let base = "otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP";
+        assert_eq!(
+            parse_error(&format!("{}&digits=six", base)),
+            "Invalid digits parameter \"six\": expected a whole number"
+        );
+        assert_eq!(
+            parse_error(&format!("{}&period=30s", base)),
+            "Invalid period parameter \"30s\": expected a whole number"
+        );
+        assert_eq!(
+            parse_error("otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP&counter=1.5"),
+            "Invalid counter parameter \"1.5\": expected a whole number"
+        );
+        assert_eq!(
+            parse_error("otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP&counter=abc"),
+            "Invalid counter parameter \"abc\": expected a whole number"
+        );
+    }
+
+    #[test]
+    fn empty_and_out_of_range_parameters_are_told_apart() {
+        let base = "otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP";
+        assert_eq!(parse_error(&format!("{}&digits=", base)), "Invalid digits parameter: value is empty");
+        assert_eq!(parse_error(&format!("{}&period=", base)), "Invalid period parameter: value is empty");
+        assert_eq!(
+            parse_error(&format!("{}&period=99999999999", base)),
+            "Invalid period parameter \"99999999999\": number is out of range"
+        );
+        // Surrounding spaces are tolerated
+        assert_eq!(parse(&format!("{}&digits=%208%20", base)).digits, 8);
+    }
+
+    #[test]
+    fn negative_counters_are_rejected() {
+        let base = "otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP";
+        assert_eq!(
+            parse_error(&format!("{}&counter=-1", base)),
+            "Invalid counter parameter \"-1\": must not be negative"
+        );
+        assert_eq!(parse(&format!("{}&counter=0", base)).counter, Some(0));
+    }
+
+    #[test]
+    fn uri_secret_is_stored_in_canonical_form() {
+        // Percent-decoding can bring back spaces and zero-width characters
+        let parsed = parse("otpauth://totp/alice?secret=jbsw%20y3dp%E2%80%8Behpk3pxp");
+        assert_eq!(parsed.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(parsed.secret, normalize_base32("jbsw y3dp\u{200b}ehpk3pxp"));
+    }
+}
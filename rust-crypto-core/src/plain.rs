@@ -0,0 +1,149 @@
+// Unencrypted vault storage, for users who turn off vault encryption
+//
+// Anyone with access to the file can read every secret in it, so the app must
+// warn loudly before switching and should offer to re-encrypt later.
+// File layout: a human-readable label line (the magic) followed by the
+// accounts as JSON. The label starts with '#', which is not a base64
+// character, so the file never decodes as an encrypted blob; decryption also
+// refuses the label outright.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use zeroize::Zeroize;
+
+use crate::account::{self, Account};
+use crate::{decrypt_bytes, stamped, timelock, CryptoError, ErrorCode, IV_LENGTH, SALT_LENGTH, TAG_LENGTH};
+
+pub(crate) const PLAIN_MAGIC: &[u8] = b"# TRUEFA UNENCRYPTED VAULT v1\n";
+
+// Decrypt an encrypted vault (as produced by `encrypt_data`) into its accounts,
+// for writing out with `store_plain`
+#[napi]
+pub fn decrypt_vault_to_plain(archive: String, mut password: String) -> napi::Result<Vec<Account>> {
+    let sealed = base64::decode(archive.trim())
+        .map_err(|e| CryptoError::new(ErrorCode::InvalidEncoding, format!("Base64 decode error: {}", e)));
+    let opened = sealed.and_then(|sealed| decrypt_bytes(&sealed, &password));
+    password.zeroize();
+
+    let mut json = opened?;
+    let accounts = serde_json::from_slice(&json)
+        .map_err(|e| napi::Error::from_reason(format!("Invalid vault data: {}", e)));
+    json.zeroize();
+    accounts
+}
+
+// Serialize accounts into the labeled, unencrypted vault format
+#[napi]
+pub fn store_plain(accounts: Vec<Account>) -> napi::Result<Buffer> {
+    plain_vault(&accounts).map(Buffer::from)
+}
+
+fn plain_vault(accounts: &[Account]) -> napi::Result<Vec<u8>> {
+    let json = serde_json::to_vec(accounts)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+    Ok([PLAIN_MAGIC, json.as_slice()].concat())
+}
+
+// Read accounts back from `store_plain` output
+#[napi]
+pub fn load_plain(data: Buffer) -> napi::Result<Vec<Account>> {
+    plain_accounts(&data)
+}
+
+fn plain_accounts(data: &[u8]) -> napi::Result<Vec<Account>> {
+    let json = data
+        .strip_prefix(PLAIN_MAGIC)
+        .ok_or_else(|| napi::Error::from_reason("Not an unencrypted TrueFA vault"))?;
+    serde_json::from_slice(json).map_err(|e| napi::Error::from_reason(format!("Invalid vault data: {}", e)))
+}
+
+// Whether `data` (file contents as text) is an encrypted TrueFA vault of any
+// format; false for unencrypted vaults, whatever else they contain, and for
+// single-account backups, which only `import_single_account` opens
+#[napi]
+pub fn is_truefa_blob(data: String) -> bool {
+    let data = data.trim();
+    if data.as_bytes().starts_with(PLAIN_MAGIC.trim_ascii_end()) {
+        return false;
+    }
+    match base64::decode(data) {
+        Ok(blob) if blob.starts_with(PLAIN_MAGIC) => false,
+        Ok(blob) if blob.starts_with(timelock::TIMELOCK_MAGIC) => {
+            blob.len() >= timelock::HEADER_LENGTH + SALT_LENGTH + IV_LENGTH + TAG_LENGTH
+        }
+        Ok(blob) if blob.starts_with(stamped::STAMPED_MAGIC) => {
+            blob.len() >= stamped::HEADER_LENGTH + SALT_LENGTH + IV_LENGTH + TAG_LENGTH
+        }
+        Ok(blob) if blob.starts_with(account::SINGLE_ACCOUNT_MAGIC) => false,
+        Ok(blob) => blob.len() >= SALT_LENGTH + IV_LENGTH + TAG_LENGTH,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts() -> Vec<Account> {
+        vec![
+            Account {
+                id: "a".to_string(),
+                issuer: "Example".to_string(),
+                secret: "JBSWY3DPEHPK3PXP".to_string(),
+                ..Default::default()
+            },
+            Account {
+                id: "b".to_string(),
+                issuer: "Other".to_string(),
+                secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string(),
+                digits: Some(8),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn plain_vault_round_trip() {
+        let stored = plain_vault(&accounts()).unwrap();
+        assert!(stored.starts_with(PLAIN_MAGIC));
+        assert_eq!(plain_accounts(&stored).unwrap(), accounts());
+        assert_eq!(plain_accounts(&plain_vault(&[]).unwrap()).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn plain_vault_is_never_taken_for_an_encrypted_blob() {
+        let stored = plain_vault(&accounts()).unwrap();
+        let text = String::from_utf8(stored.clone()).unwrap();
+        assert!(!is_truefa_blob(text));
+        assert!(!is_truefa_blob(base64::encode(&stored)));
+        assert_eq!(decrypt_bytes(&stored, "hunter2").unwrap_err().code, ErrorCode::InvalidFormat);
+    }
+
+    #[test]
+    fn single_account_backups_are_not_vaults() {
+        let mut backup = account::SINGLE_ACCOUNT_MAGIC.to_vec();
+        backup.resize(account::SINGLE_ACCOUNT_MAGIC.len() + SALT_LENGTH + IV_LENGTH + 40 + TAG_LENGTH, 0);
+        assert!(!is_truefa_blob(base64::encode(&backup)));
+
+        // Without the magic, the same bytes have the shape of a v1 vault
+        let vault = crate::encrypt_data("[]".to_string(), "hunter2".to_string());
+        assert!(is_truefa_blob(vault.data));
+        assert!(is_truefa_blob(base64::encode(&backup[account::SINGLE_ACCOUNT_MAGIC.len()..])));
+    }
+
+    #[test]
+    fn load_plain_requires_the_label() {
+        let json = serde_json::to_vec(&accounts()).unwrap();
+        assert_eq!(plain_accounts(&json).unwrap_err().reason, "Not an unencrypted TrueFA vault");
+        let broken = [PLAIN_MAGIC, b"[{".as_slice()].concat();
+        assert!(plain_accounts(&broken).is_err());
+    }
+
+    #[test]
+    fn encrypted_vault_converts_to_plain() {
+        let json = serde_json::to_string(&accounts()).unwrap();
+        let encrypted = crate::encrypt_data(json, "hunter2".to_string());
+        assert_eq!(decrypt_vault_to_plain(encrypted.data.clone(), "hunter2".to_string()).unwrap(), accounts());
+        assert!(decrypt_vault_to_plain(encrypted.data, "wrong".to_string()).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+// QR code density estimates for otpauth URIs
+//
+// Long secrets and metadata push a QR code to higher versions, which have
+// smaller modules and are harder to scan from a screen. Estimates assume the
+// whole URI is encoded as a single byte-mode segment, which is what generators
+// produce for otpauth URIs (lowercase letters rule out alphanumeric mode).
+
+use napi_derive::napi;
+
+const MAX_VERSION: usize = 40;
+
+// Data codewords per version (1..=40) for error correction levels L, M, Q, H
+// (ISO/IEC 18004, table 7)
+const DATA_CODEWORDS: [[u16; 4]; MAX_VERSION] = [
+    [19, 16, 13, 9],
+    [34, 28, 22, 16],
+    [55, 44, 34, 26],
+    [80, 64, 48, 36],
+    [108, 86, 62, 46],
+    [136, 108, 76, 60],
+    [156, 124, 88, 66],
+    [194, 154, 110, 86],
+    [232, 182, 132, 100],
+    [274, 216, 154, 122],
+    [324, 254, 180, 140],
+    [370, 290, 206, 158],
+    [428, 334, 244, 180],
+    [461, 365, 261, 197],
+    [523, 415, 295, 223],
+    [589, 453, 325, 253],
+    [647, 507, 367, 283],
+    [721, 563, 397, 313],
+    [795, 627, 445, 341],
+    [861, 669, 485, 385],
+    [932, 714, 512, 406],
+    [1006, 782, 568, 442],
+    [1094, 860, 614, 464],
+    [1174, 914, 664, 514],
+    [1276, 1000, 718, 538],
+    [1370, 1062, 754, 596],
+    [1468, 1128, 808, 628],
+    [1531, 1193, 871, 661],
+    [1631, 1267, 911, 701],
+    [1735, 1373, 985, 745],
+    [1843, 1455, 1033, 793],
+    [1955, 1541, 1115, 845],
+    [2071, 1631, 1171, 901],
+    [2191, 1725, 1231, 961],
+    [2306, 1812, 1286, 986],
+    [2434, 1914, 1354, 1054],
+    [2566, 1992, 1426, 1096],
+    [2702, 2102, 1502, 1142],
+    [2812, 2216, 1582, 1222],
+    [2956, 2334, 1666, 1276],
+];
+
+// Smallest QR version (1-40) that holds `uri` at the given error correction level
+// A version `v` code is 17 + 4v modules wide, so the result also gives its density
+#[napi]
+pub fn estimate_qr_version(uri: String, error_correction: String) -> napi::Result<u32> {
+    let level = match error_correction.trim().to_uppercase().as_str() {
+        "L" => 0,
+        "M" => 1,
+        "Q" => 2,
+        "H" => 3,
+        _ => {
+            return Err(napi::Error::from_reason(format!(
+                "Invalid error correction level: {} (expected L, M, Q or H)",
+                error_correction
+            )))
+        }
+    };
+
+    let length = uri.len();
+    (1..=MAX_VERSION)
+        .find(|&version| length <= byte_capacity(version, level))
+        .map(|version| version as u32)
+        .ok_or_else(|| napi::Error::from_reason("URI is too long to fit in a QR code"))
+}
+
+// Bytes that fit in one byte-mode segment: the 4-bit mode indicator and the
+// character count (8 bits up to version 9, 16 bits after) come out of the data bits
+fn byte_capacity(version: usize, level: usize) -> usize {
+    let data_bits = DATA_CODEWORDS[version - 1][level] as usize * 8;
+    let count_bits = if version <= 9 { 8 } else { 16 };
+    (data_bits - 4 - count_bits) / 8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(length: usize, level: &str) -> napi::Result<u32> {
+        estimate_qr_version("a".repeat(length), level.to_string())
+    }
+
+    #[test]
+    fn byte_capacities_match_the_standard() {
+        // ISO/IEC 18004 table 7, byte mode
+        assert_eq!([0, 1, 2, 3].map(|level| byte_capacity(1, level)), [17, 14, 11, 7]);
+        assert_eq!(byte_capacity(2, 0), 32);
+        // The character count grows to 16 bits at version 10
+        assert_eq!((byte_capacity(9, 0), byte_capacity(10, 0)), (230, 271));
+        assert_eq!((byte_capacity(40, 0), byte_capacity(40, 3)), (2953, 1273));
+    }
+
+    #[test]
+    fn version_steps_up_at_each_boundary() {
+        assert_eq!(version(17, "L").unwrap(), 1);
+        assert_eq!(version(18, "L").unwrap(), 2);
+        assert_eq!(version(14, "M").unwrap(), 1);
+        assert_eq!(version(15, "M").unwrap(), 2);
+        assert_eq!(version(230, "L").unwrap(), 9);
+        assert_eq!(version(231, "L").unwrap(), 10);
+        assert_eq!(version(2953, "L").unwrap(), 40);
+        // Level names are matched loosely
+        assert_eq!(version(7, " h ").unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_levels_and_oversized_uris() {
+        assert!(version(10, "X").is_err());
+        assert!(version(10, "").is_err());
+        assert!(version(2954, "L").is_err());
+        assert!(version(1274, "H").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+// Forward-secret encryption for append-only logs (e.g. the access log)
+//
+// Each entry is encrypted under its own key derived from a chaining value,
+// and the chaining value is then replaced by a one-way HKDF step. A device
+// compromised today only holds the current chaining value, which cannot be
+// run backwards, so earlier entries stay confidential.
+//
+// Limits: there is no backward secrecy. Whoever obtains the current chaining
+// value can derive every future key. The seed itself can derive every key,
+// so it belongs to the log reader (auditor) and must not be kept on the device.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use ring::rand::{SecureRandom, SystemRandom};
+use zeroize::Zeroize;
+
+use crate::{hkdf_sha256, IV_LENGTH};
+
+const CHAIN_KEY_LENGTH: usize = 32;
+const INDEX_LENGTH: usize = 4;
+// Entries per log; reading entry `i` takes `i` ratchet steps, so the index
+// taken from an entry is bounded before any work is done
+const MAX_ENTRIES: u32 = 1 << 20;
+
+// Separate HKDF labels so entry keys and chaining values never coincide
+const ENTRY_KEY_INFO: &[u8] = b"truefa-ratchet-entry";
+const CHAIN_KEY_INFO: &[u8] = b"truefa-ratchet-chain";
+
+// Encrypts log entries, ratcheting its key forward after every entry
+#[napi]
+pub struct RatchetEncryptor {
+    chain_key: [u8; CHAIN_KEY_LENGTH],
+    index: u32,
+}
+
+#[napi]
+impl RatchetEncryptor {
+    // Start a log from a 32-byte random seed
+    #[napi(constructor)]
+    pub fn new(seed: Buffer) -> napi::Result<Self> {
+        Self::from_seed(&seed)
+    }
+
+    // Index the next entry will be written with
+    #[napi(getter)]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    // Encrypt one entry and ratchet forward
+    // Output layout: index (4 bytes, big-endian) + iv + ciphertext
+    #[napi]
+    pub fn next_entry(&mut self, plaintext: String) -> napi::Result<Buffer> {
+        self.seal_next(plaintext.as_bytes()).map(Buffer::from)
+    }
+}
+
+impl RatchetEncryptor {
+    fn from_seed(seed: &[u8]) -> napi::Result<Self> {
+        Ok(Self {
+            chain_key: chain_key_from_seed(seed)?,
+            index: 0,
+        })
+    }
+
+    fn seal_next(&mut self, plaintext: &[u8]) -> napi::Result<Vec<u8>> {
+        if self.index >= MAX_ENTRIES {
+            return Err(napi::Error::from_reason("Ratchet log is full"));
+        }
+        let (mut entry_key, next_chain) = ratchet_step(&self.chain_key)?;
+
+        // Discard the previous chaining value before doing anything else with the key
+        self.chain_key.zeroize();
+        self.chain_key = next_chain;
+
+        let index = self.index;
+        self.index += 1;
+
+        let result = seal_entry(&entry_key, index, plaintext);
+        entry_key.zeroize();
+        result
+    }
+}
+
+impl Drop for RatchetEncryptor {
+    fn drop(&mut self) {
+        self.chain_key.zeroize();
+    }
+}
+
+// Decrypt a single entry given the log's original seed
+// The seed is ratcheted forward to the entry's index, so cost grows with the
+// index; entries claiming an index no log can reach are refused up front
+#[napi]
+pub fn decrypt_ratchet_entry(seed: Buffer, entry: Buffer) -> napi::Result<String> {
+    open_entry(&seed, &entry)
+}
+
+fn open_entry(seed: &[u8], entry: &[u8]) -> napi::Result<String> {
+    if entry.len() < INDEX_LENGTH + IV_LENGTH {
+        return Err(napi::Error::from_reason("Invalid ratchet entry format"));
+    }
+    let index = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+    if index >= MAX_ENTRIES {
+        return Err(napi::Error::from_reason("Invalid ratchet entry: index is out of range"));
+    }
+
+    let mut chain_key = chain_key_from_seed(seed)?;
+    for _ in 0..index {
+        let (mut entry_key, next_chain) = ratchet_step(&chain_key)?;
+        entry_key.zeroize();
+        chain_key.zeroize();
+        chain_key = next_chain;
+    }
+
+    let (mut entry_key, mut next_chain) = ratchet_step(&chain_key)?;
+    chain_key.zeroize();
+    next_chain.zeroize();
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&entry_key));
+    let result = cipher.decrypt(
+        Nonce::from_slice(&entry[INDEX_LENGTH..INDEX_LENGTH + IV_LENGTH]),
+        Payload {
+            msg: &entry[INDEX_LENGTH + IV_LENGTH..],
+            aad: &entry[..INDEX_LENGTH],
+        },
+    );
+    entry_key.zeroize();
+
+    let plaintext = result.map_err(|_| napi::Error::from_reason("Ratchet entry failed authentication"))?;
+    String::from_utf8(plaintext).map_err(|e| napi::Error::from_reason(format!("UTF-8 decode error: {}", e)))
+}
+
+fn chain_key_from_seed(seed: &[u8]) -> napi::Result<[u8; CHAIN_KEY_LENGTH]> {
+    if seed.len() != CHAIN_KEY_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid ratchet seed: expected {} bytes",
+            CHAIN_KEY_LENGTH
+        )));
+    }
+    let mut chain_key = [0u8; CHAIN_KEY_LENGTH];
+    chain_key.copy_from_slice(seed);
+    Ok(chain_key)
+}
+
+// One ratchet step: (entry key, next chaining value) from the current chaining value
+fn ratchet_step(chain_key: &[u8]) -> napi::Result<([u8; 32], [u8; CHAIN_KEY_LENGTH])> {
+    let mut entry_key = [0u8; 32];
+    let mut next_chain = [0u8; CHAIN_KEY_LENGTH];
+    hkdf_sha256(chain_key, &[], ENTRY_KEY_INFO, &mut entry_key)?;
+    hkdf_sha256(chain_key, &[], CHAIN_KEY_INFO, &mut next_chain)?;
+    Ok((entry_key, next_chain))
+}
+
+fn seal_entry(entry_key: &[u8], index: u32, plaintext: &[u8]) -> napi::Result<Vec<u8>> {
+    let mut iv = [0u8; IV_LENGTH];
+    SystemRandom::new()
+        .fill(&mut iv)
+        .map_err(|_| napi::Error::from_reason("Failed to generate nonce"))?;
+
+    // The index is authenticated so entries cannot be renumbered
+    let index_bytes = index.to_be_bytes();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(entry_key));
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&iv),
+            Payload {
+                msg: plaintext,
+                aad: &index_bytes,
+            },
+        )
+        .map_err(|e| napi::Error::from_reason(format!("Encryption error: {}", e)))?;
+
+    let mut entry = Vec::with_capacity(INDEX_LENGTH + IV_LENGTH + ciphertext.len());
+    entry.extend_from_slice(&index_bytes);
+    entry.extend_from_slice(&iv);
+    entry.extend_from_slice(&ciphertext);
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; CHAIN_KEY_LENGTH] = [7u8; CHAIN_KEY_LENGTH];
+
+    #[test]
+    fn entries_decrypt_with_the_seed() {
+        let mut log = RatchetEncryptor::from_seed(&SEED).unwrap();
+        let entries: Vec<_> = ["first", "second", "third"]
+            .iter()
+            .map(|text| log.seal_next(text.as_bytes()).unwrap())
+            .collect();
+
+        assert_eq!(log.index(), 3);
+        assert_eq!(open_entry(&SEED, &entries[0]).unwrap(), "first");
+        assert_eq!(open_entry(&SEED, &entries[2]).unwrap(), "third");
+    }
+
+    #[test]
+    fn ratcheted_state_cannot_decrypt_earlier_entries() {
+        let mut log = RatchetEncryptor::from_seed(&SEED).unwrap();
+        let first = log.seal_next(b"first").unwrap();
+        let second = log.seal_next(b"second").unwrap();
+
+        // What a device compromised now would hold
+        let current = log.chain_key;
+        assert!(open_entry(&current, &first).is_err());
+        assert!(open_entry(&current, &second).is_err());
+    }
+
+    #[test]
+    fn renumbered_entry_fails_authentication() {
+        let mut log = RatchetEncryptor::from_seed(&SEED).unwrap();
+        let mut entry = log.seal_next(b"first").unwrap();
+        entry[3] = 1;
+        assert!(open_entry(&SEED, &entry).is_err());
+    }
+
+    #[test]
+    fn rejects_short_seed() {
+        assert!(RatchetEncryptor::from_seed(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn out_of_range_indices_are_refused_without_ratcheting() {
+        let mut log = RatchetEncryptor::from_seed(&SEED).unwrap();
+        let mut entry = log.seal_next(b"first").unwrap();
+        for index in [MAX_ENTRIES, u32::MAX] {
+            entry[..INDEX_LENGTH].copy_from_slice(&index.to_be_bytes());
+            let error = open_entry(&SEED, &entry).unwrap_err();
+            assert_eq!(error.reason, "Invalid ratchet entry: index is out of range");
+        }
+    }
+
+    #[test]
+    fn log_stops_at_the_last_readable_index() {
+        let mut log = RatchetEncryptor::from_seed(&SEED).unwrap();
+        log.index = MAX_ENTRIES - 1;
+        assert!(log.seal_next(b"last").is_ok());
+        assert_eq!(log.seal_next(b"one too many").unwrap_err().reason, "Ratchet log is full");
+        assert_eq!(log.index(), MAX_ENTRIES);
+    }
+}
@@ -0,0 +1,394 @@
+// Read-only security assessment of a vault for the security dashboard
+
+use napi_derive::napi;
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::{decode_base32, HashAlgorithm, SecureSecret, PBKDF2_ITERATIONS};
+
+// RFC 4226 minimum shared secret length (128 bits)
+const MIN_SECRET_BYTES: usize = 16;
+
+// Key derivation the vault was encrypted with, as known to the app
+#[napi(object)]
+pub struct BlobInfo {
+    // e.g. "PBKDF2-SHA256"
+    pub kdf: String,
+    pub iterations: u32,
+}
+
+#[napi(string_enum)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+// One issue found in the vault, with a suggested fix for the user
+#[napi(object)]
+pub struct SecurityFinding {
+    pub id: String,
+    pub severity: Severity,
+    // Number of accounts affected (zero for vault-level findings)
+    pub count: u32,
+    pub message: String,
+    pub remediation: String,
+}
+
+#[napi(object)]
+pub struct SecurityReport {
+    pub total_accounts: u32,
+    pub sha1_accounts: u32,
+    pub stronger_algorithm_accounts: u32,
+    pub weak_secret_accounts: u32,
+    pub missing_issuer_accounts: u32,
+    pub kdf_up_to_date: bool,
+    pub findings: Vec<SecurityFinding>,
+}
+
+// Summarize the vault's security posture from account metadata and blob info
+// Secrets are only decoded to measure their length; nothing is stored or returned
+#[napi]
+pub fn vault_security_report(accounts: Vec<Account>, blob_info: BlobInfo) -> SecurityReport {
+    let mut sha1_accounts = 0;
+    let mut stronger_algorithm_accounts = 0;
+    let mut unknown_algorithm_accounts = 0;
+    let mut weak_secret_accounts = 0;
+    let mut missing_issuer_accounts = 0;
+
+    for account in &accounts {
+        match account.algorithm.as_deref().map(HashAlgorithm::from_name) {
+            None | Some(Ok(HashAlgorithm::Sha1)) => sha1_accounts += 1,
+            Some(Ok(_)) => stronger_algorithm_accounts += 1,
+            Some(Err(_)) => unknown_algorithm_accounts += 1,
+        }
+
+        if is_weak_secret(&account.secret) {
+            weak_secret_accounts += 1;
+        }
+
+        if account.issuer.trim().is_empty() {
+            missing_issuer_accounts += 1;
+        }
+    }
+
+    let kdf_up_to_date = is_current_kdf(&blob_info);
+
+    let mut findings = Vec::new();
+    if !kdf_up_to_date {
+        findings.push(SecurityFinding {
+            id: "OUTDATED_KDF".to_string(),
+            severity: Severity::High,
+            count: 0,
+            message: format!(
+                "Vault key derivation ({} with {} iterations) is weaker than the current default",
+                blob_info.kdf, blob_info.iterations
+            ),
+            remediation: "Re-save the vault with your master password to upgrade its encryption".to_string(),
+        });
+    }
+    if weak_secret_accounts > 0 {
+        findings.push(SecurityFinding {
+            id: "WEAK_SECRETS".to_string(),
+            severity: Severity::Medium,
+            count: weak_secret_accounts,
+            message: format!(
+                "{} account(s) use a secret shorter than {} bits or one that cannot be decoded",
+                weak_secret_accounts,
+                MIN_SECRET_BYTES * 8
+            ),
+            remediation: "Re-enroll 2FA with the service to obtain a new, longer secret".to_string(),
+        });
+    }
+    if unknown_algorithm_accounts > 0 {
+        findings.push(SecurityFinding {
+            id: "UNKNOWN_ALGORITHM".to_string(),
+            severity: Severity::Medium,
+            count: unknown_algorithm_accounts,
+            message: format!("{} account(s) use an unsupported algorithm", unknown_algorithm_accounts),
+            remediation: "Edit the account and select SHA1, SHA256 or SHA512".to_string(),
+        });
+    }
+    if sha1_accounts > 0 {
+        // HMAC-SHA1 is still sound for TOTP; this is informational only
+        findings.push(SecurityFinding {
+            id: "SHA1_ACCOUNTS".to_string(),
+            severity: Severity::Info,
+            count: sha1_accounts,
+            message: format!("{} account(s) use HMAC-SHA1", sha1_accounts),
+            remediation: "No action needed; switch to SHA256 if the service offers it".to_string(),
+        });
+    }
+    if missing_issuer_accounts > 0 {
+        findings.push(SecurityFinding {
+            id: "MISSING_ISSUER".to_string(),
+            severity: Severity::Low,
+            count: missing_issuer_accounts,
+            message: format!("{} account(s) have no issuer", missing_issuer_accounts),
+            remediation: "Add the service name so codes are not used for the wrong site".to_string(),
+        });
+    }
+
+    SecurityReport {
+        total_accounts: accounts.len() as u32,
+        sha1_accounts,
+        stronger_algorithm_accounts,
+        weak_secret_accounts,
+        missing_issuer_accounts,
+        kdf_up_to_date,
+        findings,
+    }
+}
+
+// One deduction from the health score
+#[napi(object)]
+pub struct HealthFactor {
+    // Finding id from `vault_security_report`, or "NO_BACKUP" / "STALE_BACKUP"
+    pub id: String,
+    // Points taken off the score
+    pub penalty: u32,
+    // Accounts affected (zero for vault-level factors)
+    pub count: u32,
+    pub message: String,
+}
+
+#[napi(object)]
+pub struct HealthScore {
+    // 0 (worst) to 100 (no issues found)
+    pub score: u32,
+    // Largest penalty first
+    pub factors: Vec<HealthFactor>,
+}
+
+// Most points each factor can cost. Vault-level factors always cost the full
+// amount; account-level ones cost their share of affected accounts, rounded up
+// so a single affected account still shows. All together they add up to 100.
+const HEALTH_WEIGHTS: [(&str, u32); 7] = [
+    ("OUTDATED_KDF", 30),
+    ("WEAK_SECRETS", 30),
+    ("UNKNOWN_ALGORITHM", 15),
+    ("MISSING_ISSUER", 10),
+    ("SHA1_ACCOUNTS", 5),
+    ("NO_BACKUP", 10),
+    // Replaces NO_BACKUP when a backup exists but is old
+    ("STALE_BACKUP", 5),
+];
+
+// A backup older than this no longer counts as current
+const BACKUP_MAX_AGE_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+
+// Single 0-100 score for the dashboard with the factors that lowered it
+// Built from `vault_security_report` plus the backup state; `last_backup` is
+// the Unix time in milliseconds of the latest export, absent if there never was one.
+// `timestamp` (Unix seconds, defaults to the current time) is the moment the
+// backup age is measured at. An empty vault scores 100 apart from the
+// vault-level factors.
+#[napi]
+pub fn vault_health_score(
+    accounts: Vec<Account>,
+    blob_info: BlobInfo,
+    last_backup: Option<i64>,
+    timestamp: Option<i64>,
+) -> HealthScore {
+    let has_accounts = !accounts.is_empty();
+    let report = vault_security_report(accounts, blob_info);
+    let total = report.total_accounts;
+
+    let mut factors: Vec<HealthFactor> = report
+        .findings
+        .into_iter()
+        .map(|finding| {
+            let penalty = if finding.count == 0 {
+                health_weight(&finding.id)
+            } else {
+                (health_weight(&finding.id) * finding.count).div_ceil(total)
+            };
+            HealthFactor {
+                id: finding.id,
+                penalty,
+                count: finding.count,
+                message: finding.message,
+            }
+        })
+        .collect();
+
+    if has_accounts {
+        let now = timestamp.unwrap_or_else(crate::current_timestamp).saturating_mul(1000);
+        let backup = match last_backup {
+            None => Some(("NO_BACKUP", "The vault has never been backed up")),
+            Some(time) if now - time > BACKUP_MAX_AGE_MS => Some(("STALE_BACKUP", "The last backup is over 90 days old")),
+            Some(_) => None,
+        };
+        if let Some((id, message)) = backup {
+            factors.push(HealthFactor {
+                id: id.to_string(),
+                penalty: health_weight(id),
+                count: 0,
+                message: message.to_string(),
+            });
+        }
+    }
+
+    factors.retain(|factor| factor.penalty > 0);
+    factors.sort_by_key(|factor| std::cmp::Reverse(factor.penalty));
+    let deducted: u32 = factors.iter().map(|factor| factor.penalty).sum();
+
+    HealthScore {
+        score: 100u32.saturating_sub(deducted),
+        factors,
+    }
+}
+
+fn health_weight(id: &str) -> u32 {
+    HEALTH_WEIGHTS
+        .iter()
+        .find(|(name, _)| *name == id)
+        .map_or(0, |(_, weight)| *weight)
+}
+
+// Recommended minimum secret length in bytes for an HMAC algorithm: the size of
+// its output (20, 32 or 64), as RFC 6238's reference keys use. Secrets shorter
+// than this still work and are only flagged as weak below `MIN_SECRET_BYTES`.
+#[napi]
+pub fn recommended_secret_length(algorithm: String) -> napi::Result<u32> {
+    Ok(HashAlgorithm::from_name(&algorithm)?.digest_len() as u32)
+}
+
+// Estimated entropy of a secret in bits
+// A randomly generated secret scores its full length (8 bits per byte). For
+// imported secrets this is only a heuristic that catches obvious patterns:
+// a short block repeated (including a single repeated byte) only counts for the
+// block, and a constant-step run such as 00 01 02 ... counts as 16 bits
+// (start and step). Passing it does not prove a secret is random.
+#[napi]
+pub fn secret_entropy_bits(secret: &SecureSecret) -> napi::Result<u32> {
+    Ok(estimate_entropy_bits(secret.bytes()?))
+}
+
+fn estimate_entropy_bits(bytes: &[u8]) -> u32 {
+    if bytes.is_empty() {
+        return 0;
+    }
+
+    let step = bytes[1..].first().map(|&second| second.wrapping_sub(bytes[0]));
+    let constant_step = step.is_some_and(|step| {
+        step != 0 && bytes.windows(2).all(|pair| pair[1].wrapping_sub(pair[0]) == step)
+    });
+    if constant_step {
+        return 16;
+    }
+
+    // Shortest block whose repetition produces the whole secret
+    let period = (1..=bytes.len())
+        .find(|&period| bytes.iter().enumerate().all(|(i, &byte)| byte == bytes[i % period]))
+        .unwrap_or(bytes.len());
+    (period * 8) as u32
+}
+
+fn is_weak_secret(secret: &str) -> bool {
+    match decode_base32(secret) {
+        Some(mut bytes) => {
+            let weak = bytes.len() < MIN_SECRET_BYTES;
+            bytes.zeroize();
+            weak
+        }
+        None => true,
+    }
+}
+
+// The vault is current when it uses the default KDF with at least the default cost
+fn is_current_kdf(blob_info: &BlobInfo) -> bool {
+    let kdf = blob_info.kdf.trim().to_uppercase().replace(['-', '_'], "");
+    kdf == "PBKDF2SHA256" && blob_info.iterations >= PBKDF2_ITERATIONS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    fn healthy_account() -> Account {
+        Account {
+            issuer: "Example".to_string(),
+            // 20 bytes
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string(),
+            algorithm: Some("SHA256".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn current_kdf() -> BlobInfo {
+        BlobInfo {
+            kdf: "PBKDF2-SHA256".to_string(),
+            iterations: PBKDF2_ITERATIONS,
+        }
+    }
+
+    fn health(accounts: Vec<Account>, blob_info: BlobInfo, last_backup: Option<i64>) -> (u32, Vec<(String, u32)>) {
+        let score = vault_health_score(accounts, blob_info, last_backup, Some(NOW));
+        let factors = score.factors.into_iter().map(|factor| (factor.id, factor.penalty)).collect();
+        (score.score, factors)
+    }
+
+    #[test]
+    fn health_score_of_a_healthy_vault() {
+        let recent = Some(NOW * 1000 - DAY_MS);
+        assert_eq!(health(vec![healthy_account(); 3], current_kdf(), recent), (100, Vec::new()));
+        // Vault-level factors only count for a vault with accounts
+        assert_eq!(health(Vec::new(), current_kdf(), None), (100, Vec::new()));
+    }
+
+    #[test]
+    fn health_score_measures_backup_age_at_the_given_time() {
+        let accounts = vec![healthy_account()];
+        let no_backup = health(accounts.clone(), current_kdf(), None);
+        assert_eq!(no_backup, (90, vec![("NO_BACKUP".to_string(), 10)]));
+
+        let backup = NOW * 1000 - 90 * DAY_MS;
+        assert_eq!(health(accounts.clone(), current_kdf(), Some(backup)).0, 100);
+        let stale = health(accounts, current_kdf(), Some(backup - 1));
+        assert_eq!(stale, (95, vec![("STALE_BACKUP".to_string(), 5)]));
+    }
+
+    #[test]
+    fn health_score_penalties_scale_with_affected_accounts() {
+        let weak = Account {
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            ..healthy_account()
+        };
+        let outdated = BlobInfo {
+            kdf: "PBKDF2-SHA256".to_string(),
+            iterations: 100_000,
+        };
+        let recent = Some(NOW * 1000);
+
+        // One of three accounts weak: 30 / 3 = 10 points
+        assert_eq!(
+            health(vec![weak.clone(), healthy_account(), healthy_account()], current_kdf(), recent),
+            (90, vec![("WEAK_SECRETS".to_string(), 10)])
+        );
+        // Rounded up, largest penalty first
+        assert_eq!(
+            health(vec![weak, healthy_account()], outdated, recent),
+            (55, vec![("OUTDATED_KDF".to_string(), 30), ("WEAK_SECRETS".to_string(), 15)])
+        );
+    }
+
+    #[test]
+    fn recommended_length_is_the_digest_size() {
+        assert_eq!(recommended_secret_length("SHA1".to_string()).unwrap(), 20);
+        assert_eq!(recommended_secret_length("sha-256".to_string()).unwrap(), 32);
+        assert_eq!(recommended_secret_length(" SHA512 ".to_string()).unwrap(), 64);
+        assert!(recommended_secret_length("MD5".to_string()).is_err());
+    }
+
+    #[test]
+    fn recommended_length_is_never_below_the_weak_threshold() {
+        for algorithm in ["SHA1", "SHA256", "SHA512"] {
+            assert!(recommended_secret_length(algorithm.to_string()).unwrap() as usize >= MIN_SECRET_BYTES);
+        }
+    }
+}
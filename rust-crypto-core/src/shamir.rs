@@ -0,0 +1,238 @@
+// Shamir's Secret Sharing over GF(256) for splitting a vault key among trustees
+//
+// Every byte of the key is the constant term of its own random polynomial of
+// degree `threshold - 1`; share `x` holds the polynomial values at `x`.
+// Share layout: threshold (1 byte) + x coordinate (1 byte) + one y byte per key byte.
+// Fewer than `threshold` shares reveal nothing about the key.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use ring::rand::{SecureRandom, SystemRandom};
+use zeroize::Zeroize;
+
+use crate::keys::{DerivedKey, KEY_LENGTH};
+
+const SHARE_HEADER_LENGTH: usize = 2;
+const MIN_THRESHOLD: u32 = 2;
+// x = 0 would be the key itself, so at most 255 distinct share coordinates exist
+const MAX_SHARES: u32 = 255;
+
+// Split a 32-byte key into `shares` shares, any `threshold` of which reconstruct it
+#[napi]
+pub fn split_vault_key(dek: Buffer, threshold: u32, shares: u32) -> napi::Result<Vec<Buffer>> {
+    let outputs = split_key(&dek, threshold, shares)?;
+    Ok(outputs.into_iter().map(Buffer::from).collect())
+}
+
+// Reconstruct a key from at least `threshold` shares produced by `split_vault_key`
+// The key is loaded straight into a `DerivedKey`, so its bytes never reach JS
+#[napi]
+pub fn combine_vault_key(shares: Vec<Buffer>) -> napi::Result<DerivedKey> {
+    let mut key = combine_shares(&shares)?;
+    let derived = DerivedKey::from_key(&key);
+    key.zeroize();
+    derived
+}
+
+fn split_key(dek: &[u8], threshold: u32, shares: u32) -> napi::Result<Vec<Vec<u8>>> {
+    if threshold < MIN_THRESHOLD {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid threshold: must be at least {}",
+            MIN_THRESHOLD
+        )));
+    }
+    if threshold > shares {
+        return Err(napi::Error::from_reason("Invalid threshold: cannot exceed the number of shares"));
+    }
+    if shares > MAX_SHARES {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid share count: at most {} shares are supported",
+            MAX_SHARES
+        )));
+    }
+    // Only a full-length key can be loaded back into a `DerivedKey`
+    if dek.len() != KEY_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Invalid key: expected {} bytes",
+            KEY_LENGTH
+        )));
+    }
+
+    let mut outputs: Vec<Vec<u8>> = (1..=shares)
+        .map(|x| {
+            let mut share = Vec::with_capacity(SHARE_HEADER_LENGTH + dek.len());
+            share.push(threshold as u8);
+            share.push(x as u8);
+            share
+        })
+        .collect();
+
+    // coefficients[0] is the key byte, the rest are random
+    let rng = SystemRandom::new();
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &secret_byte in dek {
+        coefficients[0] = secret_byte;
+        rng.fill(&mut coefficients[1..])
+            .map_err(|_| napi::Error::from_reason("Failed to generate random values"))?;
+
+        for share in outputs.iter_mut() {
+            let x = share[1];
+            share.push(evaluate(&coefficients, x));
+        }
+    }
+    coefficients.zeroize();
+
+    Ok(outputs)
+}
+
+fn combine_shares<S: AsRef<[u8]>>(shares: &[S]) -> napi::Result<Vec<u8>> {
+    let first = shares
+        .first()
+        .map(AsRef::as_ref)
+        .ok_or_else(|| napi::Error::from_reason("No shares provided"))?;
+    if first.len() <= SHARE_HEADER_LENGTH {
+        return Err(napi::Error::from_reason("Invalid share format"));
+    }
+
+    // The header is not authenticated: a forged threshold of 0 or 1 would
+    // "reconstruct" an all-zero key or hand back one share's raw bytes
+    let threshold = first[0] as usize;
+    if threshold < MIN_THRESHOLD as usize {
+        return Err(napi::Error::from_reason("Invalid share format"));
+    }
+    let key_length = first.len() - SHARE_HEADER_LENGTH;
+    if shares.len() < threshold {
+        return Err(napi::Error::from_reason(format!(
+            "Not enough shares: {} of {} required",
+            shares.len(),
+            threshold
+        )));
+    }
+
+    let mut xs = Vec::with_capacity(threshold);
+    for share in shares.iter().map(AsRef::as_ref) {
+        if share.len() != first.len() || share[0] as usize != threshold {
+            return Err(napi::Error::from_reason("Shares belong to different splits"));
+        }
+        let x = share[1];
+        if x == 0 || xs.contains(&x) {
+            return Err(napi::Error::from_reason("Invalid or duplicate share"));
+        }
+        xs.push(x);
+    }
+
+    // Any `threshold` shares determine the polynomial; extra shares are ignored
+    let shares = &shares[..threshold];
+    let xs = &xs[..threshold];
+
+    // Lagrange basis values at x = 0 depend only on the coordinates
+    let basis: Vec<u8> = (0..threshold)
+        .map(|i| {
+            let mut numerator = 1u8;
+            let mut denominator = 1u8;
+            for j in 0..threshold {
+                if i != j {
+                    numerator = gf_mul(numerator, xs[j]);
+                    denominator = gf_mul(denominator, xs[i] ^ xs[j]);
+                }
+            }
+            gf_mul(numerator, gf_inv(denominator))
+        })
+        .collect();
+
+    let mut key = vec![0u8; key_length];
+    for (byte_index, key_byte) in key.iter_mut().enumerate() {
+        for (share, &weight) in shares.iter().zip(&basis) {
+            *key_byte ^= gf_mul(share.as_ref()[SHARE_HEADER_LENGTH + byte_index], weight);
+        }
+    }
+
+    Ok(key)
+}
+
+// Horner evaluation of the polynomial at `x`
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0u8, |acc, &coefficient| gf_mul(acc, x) ^ coefficient)
+}
+
+// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without secret-dependent branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+// Multiplicative inverse as a^254 (a^255 = 1 for non-zero a)
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [0xa5; 32];
+
+    #[test]
+    fn any_threshold_shares_reconstruct_the_key() {
+        let shares = split_key(&KEY, 3, 5).unwrap();
+        assert_eq!(combine_shares(&shares[..3]).unwrap(), KEY);
+        assert_eq!(combine_shares(&[&shares[4], &shares[1], &shares[2]]).unwrap(), KEY);
+        assert_eq!(combine_shares(&shares).unwrap(), KEY);
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_fail() {
+        let shares = split_key(&KEY, 3, 5).unwrap();
+        let error = combine_shares(&shares[..2]).unwrap_err();
+        assert_eq!(error.reason, "Not enough shares: 2 of 3 required");
+    }
+
+    #[test]
+    fn rejects_invalid_splits() {
+        assert!(split_key(&KEY, 1, 5).is_err());
+        assert!(split_key(&KEY, 6, 5).is_err());
+        assert!(split_key(&KEY, 2, 256).is_err());
+        assert!(split_key(&[], 2, 3).is_err());
+        // A 16-byte key would split but could never be combined into a `DerivedKey`
+        assert!(split_key(&[0xa5; 16], 2, 3).is_err());
+
+        let shares = split_key(&KEY, 2, 3).unwrap();
+        assert!(combine_shares(&[&shares[0], &shares[0]]).is_err());
+    }
+
+    #[test]
+    fn forged_low_thresholds_are_rejected() {
+        let shares = split_key(&KEY, 2, 3).unwrap();
+        for forged_threshold in [0u8, 1] {
+            let mut forged = shares[0].clone();
+            forged[0] = forged_threshold;
+            assert_eq!(combine_shares(&[&forged]).unwrap_err().reason, "Invalid share format");
+        }
+    }
+
+    #[test]
+    fn gf_inverse_round_trips() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+}
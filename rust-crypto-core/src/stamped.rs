@@ -0,0 +1,149 @@
+// Encrypted vaults carrying creation and modification times in the clear
+//
+// The app can show "last backed up" and sort backups by date without the
+// password. Both times are authenticated with the ciphertext, so editing them
+// makes decryption fail.
+// Blob layout: magic (4 bytes) + created (8 bytes) + modified (8 bytes, both
+// big-endian Unix seconds) + the usual salt + iv + ciphertext.
+
+use napi_derive::napi;
+
+use crate::{current_timestamp, decrypt_bytes_bound, encrypt_bytes_bound, CryptoError, CryptoResult, ErrorCode};
+
+pub(crate) const STAMPED_MAGIC: &[u8; 4] = b"TFS1";
+pub(crate) const HEADER_LENGTH: usize = STAMPED_MAGIC.len() + 16;
+
+// Times stored in a stamped vault header
+#[napi(object)]
+pub struct VaultTimestamps {
+    pub created: i64,
+    pub modified: i64,
+}
+
+// Encrypt `data` with a timestamped header
+// `modified_at` defaults to now. When re-saving an existing vault, pass the
+// `created` value from `vault_timestamps` so it is carried over; for a new
+// vault leave it out and it equals `modified_at`.
+#[napi]
+pub fn encrypt_data_stamped(
+    data: String,
+    password: String,
+    created_at: Option<i64>,
+    modified_at: Option<i64>,
+) -> CryptoResult {
+    let modified = modified_at.unwrap_or_else(current_timestamp);
+    let header = header(created_at.unwrap_or(modified), modified);
+    match encrypt_bytes_bound(data.as_bytes(), &password, &header) {
+        Ok(sealed) => CryptoResult::success(base64::encode([header.as_slice(), &sealed].concat())),
+        Err(error) => CryptoResult::failure(error),
+    }
+}
+
+// Decrypt a stamped vault; fails if the header was modified
+#[napi]
+pub fn decrypt_data_stamped(encrypted_data: String, password: String) -> CryptoResult {
+    match open_stamped(&encrypted_data, &password) {
+        Ok(data) => CryptoResult::success(data),
+        Err(error) => CryptoResult::failure(error),
+    }
+}
+
+// Read the times of a stamped vault without the password
+// They are only proven genuine once the vault decrypts, so treat them as display hints
+#[napi]
+pub fn vault_timestamps(archive: String) -> napi::Result<VaultTimestamps> {
+    let blob = decode_stamped(&archive)?;
+    Ok(read_header(&blob))
+}
+
+fn open_stamped(encrypted_data: &str, password: &str) -> Result<String, CryptoError> {
+    let blob = decode_stamped(encrypted_data)?;
+    let plaintext = decrypt_bytes_bound(&blob[HEADER_LENGTH..], password, &blob[..HEADER_LENGTH])?;
+    String::from_utf8(plaintext)
+        .map_err(|e| CryptoError::new(ErrorCode::InvalidUtf8, format!("UTF-8 decode error: {}", e)))
+}
+
+fn decode_stamped(encoded: &str) -> Result<Vec<u8>, CryptoError> {
+    let blob = base64::decode(encoded)
+        .map_err(|e| CryptoError::new(ErrorCode::InvalidEncoding, format!("Base64 decode error: {}", e)))?;
+    if blob.len() < HEADER_LENGTH || &blob[..STAMPED_MAGIC.len()] != STAMPED_MAGIC {
+        return Err(CryptoError::new(ErrorCode::InvalidFormat, "Not a timestamped vault"));
+    }
+    Ok(blob)
+}
+
+fn header(created: i64, modified: i64) -> [u8; HEADER_LENGTH] {
+    let mut header = [0u8; HEADER_LENGTH];
+    header[..STAMPED_MAGIC.len()].copy_from_slice(STAMPED_MAGIC);
+    header[STAMPED_MAGIC.len()..STAMPED_MAGIC.len() + 8].copy_from_slice(&created.to_be_bytes());
+    header[STAMPED_MAGIC.len() + 8..].copy_from_slice(&modified.to_be_bytes());
+    header
+}
+
+fn read_header(blob: &[u8]) -> VaultTimestamps {
+    let time_at = |offset: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&blob[offset..offset + 8]);
+        i64::from_be_bytes(bytes)
+    };
+    VaultTimestamps {
+        created: time_at(STAMPED_MAGIC.len()),
+        modified: time_at(STAMPED_MAGIC.len() + 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREATED: i64 = 1_700_000_000;
+    const MODIFIED: i64 = 1_700_086_400;
+
+    fn stamped() -> String {
+        let encrypted = encrypt_data_stamped("vault".to_string(), "hunter2".to_string(), Some(CREATED), Some(MODIFIED));
+        assert!(encrypted.success);
+        encrypted.data
+    }
+
+    #[test]
+    fn round_trip() {
+        let result = decrypt_data_stamped(stamped(), "hunter2".to_string());
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.data, "vault");
+        assert!(!decrypt_data_stamped(stamped(), "hunter3".to_string()).success);
+    }
+
+    #[test]
+    fn timestamps_read_back_without_the_password() {
+        let times = vault_timestamps(stamped()).unwrap();
+        assert_eq!((times.created, times.modified), (CREATED, MODIFIED));
+        assert!(vault_timestamps(crate::encrypt_data("vault".to_string(), "hunter2".to_string()).data).is_err());
+    }
+
+    #[test]
+    fn resaving_keeps_created_and_updates_modified() {
+        let created = vault_timestamps(stamped()).unwrap().created;
+        let before = current_timestamp();
+        let resaved = encrypt_data_stamped("vault v2".to_string(), "hunter2".to_string(), Some(created), None);
+
+        let times = vault_timestamps(resaved.data.clone()).unwrap();
+        assert_eq!(times.created, CREATED);
+        assert!(times.modified >= before);
+        // A new vault starts out with both times equal
+        let fresh = encrypt_data_stamped("vault".to_string(), "hunter2".to_string(), None, Some(MODIFIED));
+        let times = vault_timestamps(fresh.data).unwrap();
+        assert_eq!((times.created, times.modified), (MODIFIED, MODIFIED));
+    }
+
+    #[test]
+    fn editing_either_timestamp_breaks_decryption() {
+        for offset in [STAMPED_MAGIC.len(), STAMPED_MAGIC.len() + 8] {
+            let mut blob = base64::decode(stamped()).unwrap();
+            blob[offset + 7] ^= 1;
+
+            let result = decrypt_data_stamped(base64::encode(blob), "hunter2".to_string());
+            assert!(!result.success);
+            assert_eq!(result.error_code.as_deref(), Some("AUTHENTICATION_FAILED"));
+        }
+    }
+}
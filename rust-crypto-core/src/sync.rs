@@ -0,0 +1,214 @@
+// Incremental vault sync: compute, encrypt and apply differences between vault versions
+
+use std::collections::{HashMap, HashSet};
+
+use data_encoding::HEXLOWER;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::{decrypt_bytes, encrypt_bytes, CryptoResult};
+
+// Changes turning one account list into another, keyed by account id
+#[napi(object)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultDiff {
+    pub added: Vec<Account>,
+    pub removed: Vec<String>,
+    pub modified: Vec<Account>,
+    // Ids of the new account list in order, so applying restores ordering too
+    pub order: Vec<String>,
+}
+
+// Compute the changes from `old_accounts` to `new_accounts`
+#[napi]
+pub fn vault_diff(old_accounts: Vec<Account>, new_accounts: Vec<Account>) -> napi::Result<VaultDiff> {
+    let old_by_id = index_by_id(&old_accounts)?;
+    let new_ids = index_by_id(&new_accounts)?;
+
+    let mut diff = VaultDiff::default();
+    for account in &new_accounts {
+        match old_by_id.get(account.id.as_str()) {
+            None => diff.added.push(account.clone()),
+            Some(previous) if *previous != account => diff.modified.push(account.clone()),
+            Some(_) => {}
+        }
+        diff.order.push(account.id.clone());
+    }
+    diff.removed = old_accounts
+        .iter()
+        .filter(|account| !new_ids.contains_key(account.id.as_str()))
+        .map(|account| account.id.clone())
+        .collect();
+
+    Ok(diff)
+}
+
+// Apply a diff from `vault_diff` to the account list it was computed against
+#[napi]
+pub fn apply_vault_diff(old_accounts: Vec<Account>, diff: VaultDiff) -> napi::Result<Vec<Account>> {
+    index_by_id(&old_accounts)?;
+    let removed: HashSet<&str> = diff.removed.iter().map(String::as_str).collect();
+
+    let mut by_id: HashMap<String, Account> = old_accounts
+        .into_iter()
+        .filter(|account| !removed.contains(account.id.as_str()))
+        .map(|account| (account.id.clone(), account))
+        .collect();
+    for account in diff.modified.into_iter().chain(diff.added) {
+        by_id.insert(account.id.clone(), account);
+    }
+
+    let mut result = Vec::with_capacity(diff.order.len());
+    for id in &diff.order {
+        let account = by_id
+            .remove(id)
+            .ok_or_else(|| napi::Error::from_reason(format!("Diff references unknown account: {}", id)))?;
+        result.push(account);
+    }
+    if !by_id.is_empty() {
+        return Err(napi::Error::from_reason("Diff does not match the base vault"));
+    }
+
+    Ok(result)
+}
+
+// Encrypt a diff for upload, using the same format as `encrypt_data`
+#[napi]
+pub fn encrypt_vault_diff(diff: VaultDiff, password: String) -> napi::Result<CryptoResult> {
+    let mut json = serde_json::to_vec(&diff)
+        .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))?;
+    let result = encrypt_bytes(&json, &password);
+    json.zeroize();
+
+    Ok(match result {
+        Ok(sealed) => CryptoResult::success(base64::encode(&sealed)),
+        Err(error) => CryptoResult::failure(error),
+    })
+}
+
+// Decrypt a diff produced by `encrypt_vault_diff`
+#[napi]
+pub fn decrypt_vault_diff(encrypted_data: String, password: String) -> napi::Result<VaultDiff> {
+    let sealed = base64::decode(&encrypted_data)
+        .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+    let mut json = decrypt_bytes(&sealed, &password)?;
+    let diff = serde_json::from_slice(&json)
+        .map_err(|e| napi::Error::from_reason(format!("Invalid vault diff: {}", e)));
+    json.zeroize();
+
+    diff
+}
+
+// Version token for an encrypted vault, for detecting concurrent modifications
+// It is SHA-256 over the encrypted bytes, so no password is needed. Every
+// re-encryption picks a fresh salt and iv, so the tag changes on each save even if
+// the accounts did not; use `vault_diff` on the decrypted lists to compare contents.
+#[napi]
+pub fn vault_sync_tag(encrypted_vault: String) -> napi::Result<String> {
+    let sealed = base64::decode(encrypted_vault.trim())
+        .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+    Ok(HEXLOWER.encode(&Sha256::digest(&sealed)))
+}
+
+// Blob format version both sides of a sync can read, for the writer to use
+// Each version's reader also reads every earlier version, so this is the lower
+// of the two maximums; pass the highest entry of `crate_info().blob_formats`
+// for each device. The newer device then writes in the older device's format.
+#[napi]
+pub fn negotiate_format(local_max_version: u32, remote_max_version: u32) -> napi::Result<u32> {
+    if local_max_version == 0 || remote_max_version == 0 {
+        return Err(napi::Error::from_reason("Format versions start at 1"));
+    }
+    Ok(local_max_version.min(remote_max_version))
+}
+
+fn index_by_id(accounts: &[Account]) -> napi::Result<HashMap<&str, &Account>> {
+    let mut by_id = HashMap::with_capacity(accounts.len());
+    for account in accounts {
+        if by_id.insert(account.id.as_str(), account).is_some() {
+            return Err(napi::Error::from_reason(format!("Duplicate account id: {}", account.id)));
+        }
+    }
+    Ok(by_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(id: &str, name: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: name.to_string(),
+            issuer: "Example".to_string(),
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn applying_a_diff_yields_the_new_list() {
+        let old = vec![account("a", "alice"), account("b", "bob"), account("c", "carol")];
+        let new = vec![account("c", "carol"), account("d", "dave"), account("a", "alice2")];
+
+        let diff = vault_diff(old.clone(), new.clone()).unwrap();
+        assert_eq!(diff.removed, vec!["b"]);
+        assert_eq!(diff.added, vec![account("d", "dave")]);
+        assert_eq!(diff.modified, vec![account("a", "alice2")]);
+        assert_eq!(apply_vault_diff(old, diff).unwrap(), new);
+    }
+
+    #[test]
+    fn empty_diff_keeps_the_list() {
+        let accounts = vec![account("a", "alice"), account("b", "bob")];
+        let diff = vault_diff(accounts.clone(), accounts.clone()).unwrap();
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty());
+        assert_eq!(apply_vault_diff(accounts.clone(), diff).unwrap(), accounts);
+    }
+
+    #[test]
+    fn diff_against_the_wrong_base_fails() {
+        let old = vec![account("a", "alice")];
+        let diff = vault_diff(old, vec![account("a", "alice"), account("b", "bob")]).unwrap();
+        assert!(apply_vault_diff(vec![account("x", "xavier")], diff).is_err());
+        assert!(vault_diff(vec![account("a", "1"), account("a", "2")], Vec::new()).is_err());
+    }
+
+    #[test]
+    fn encrypted_diff_round_trip() {
+        let diff = vault_diff(vec![account("a", "alice")], vec![account("b", "bob")]).unwrap();
+        let encrypted = encrypt_vault_diff(diff.clone(), "hunter2".to_string()).unwrap();
+        assert!(encrypted.success);
+        let decrypted = decrypt_vault_diff(encrypted.data, "hunter2".to_string()).unwrap();
+        assert_eq!((decrypted.added, decrypted.removed, decrypted.order), (diff.added, diff.removed, diff.order));
+    }
+
+    #[test]
+    fn negotiation_picks_the_older_side_either_way() {
+        assert_eq!(negotiate_format(2, 1).unwrap(), 1);
+        assert_eq!(negotiate_format(1, 2).unwrap(), 1);
+        assert_eq!(negotiate_format(2, 2).unwrap(), 2);
+        // A remote from a future build falls back to what this build writes
+        assert_eq!(negotiate_format(2, 7).unwrap(), 2);
+    }
+
+    #[test]
+    fn negotiation_rejects_version_zero() {
+        assert!(negotiate_format(0, 1).is_err());
+        assert!(negotiate_format(1, 0).is_err());
+    }
+
+    #[test]
+    fn negotiation_result_is_a_format_this_build_knows() {
+        let formats = crate::crate_info().blob_formats;
+        assert_eq!(formats, vec![1, 2]);
+        let newest = *formats.last().unwrap();
+        for remote in 1..=newest + 1 {
+            assert!(formats.contains(&negotiate_format(newest, remote).unwrap()));
+        }
+    }
+}
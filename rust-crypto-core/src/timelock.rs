@@ -0,0 +1,112 @@
+// Time-gated encryption for "time capsule" sharing
+//
+// The unlock time is stored in the clear and authenticated together with the
+// ciphertext, so it cannot be moved earlier without breaking decryption.
+// Blob layout: magic (4 bytes) + unlock time (8 bytes, big-endian Unix seconds)
+// + the usual salt + iv + ciphertext.
+//
+// This is not a cryptographic time-lock: anyone holding the password and a
+// modified client can decrypt at any time. It is only as strong as the trusted
+// time source passed to `decrypt_data_timelocked` and the code enforcing it.
+
+use napi_derive::napi;
+
+use crate::{decrypt_bytes_bound, encrypt_bytes_bound, CryptoError, CryptoResult, ErrorCode};
+
+pub(crate) const TIMELOCK_MAGIC: &[u8; 4] = b"TFT1";
+pub(crate) const HEADER_LENGTH: usize = TIMELOCK_MAGIC.len() + 8;
+
+// Encrypt `data` so that `decrypt_data_timelocked` refuses it before `unlock_after`
+#[napi]
+pub fn encrypt_data_timelocked(data: String, password: String, unlock_after: i64) -> CryptoResult {
+    let header = header(unlock_after);
+    match encrypt_bytes_bound(data.as_bytes(), &password, &header) {
+        Ok(sealed) => CryptoResult::success(base64::encode([header.as_slice(), &sealed].concat())),
+        Err(error) => CryptoResult::failure(error),
+    }
+}
+
+// Decrypt a time-locked blob, given the current time from a trusted source
+// (e.g. a signed server timestamp, not the device clock)
+#[napi]
+pub fn decrypt_data_timelocked(encrypted_data: String, password: String, trusted_time: i64) -> CryptoResult {
+    match open_timelocked(&encrypted_data, &password, trusted_time) {
+        Ok(data) => CryptoResult::success(data),
+        Err(error) => CryptoResult::failure(error),
+    }
+}
+
+fn open_timelocked(encrypted_data: &str, password: &str, trusted_time: i64) -> Result<String, CryptoError> {
+    let blob = base64::decode(encrypted_data)
+        .map_err(|e| CryptoError::new(ErrorCode::InvalidEncoding, format!("Base64 decode error: {}", e)))?;
+    if blob.len() < HEADER_LENGTH || &blob[..TIMELOCK_MAGIC.len()] != TIMELOCK_MAGIC {
+        return Err(CryptoError::new(ErrorCode::InvalidFormat, "Not a time-locked blob"));
+    }
+
+    let mut time_bytes = [0u8; 8];
+    time_bytes.copy_from_slice(&blob[TIMELOCK_MAGIC.len()..HEADER_LENGTH]);
+    let unlock_after = i64::from_be_bytes(time_bytes);
+    if trusted_time < unlock_after {
+        return Err(CryptoError::new(
+            ErrorCode::TimeLocked,
+            format!("Locked until {} (Unix time)", unlock_after),
+        ));
+    }
+
+    let plaintext = decrypt_bytes_bound(&blob[HEADER_LENGTH..], password, &blob[..HEADER_LENGTH])?;
+    String::from_utf8(plaintext)
+        .map_err(|e| CryptoError::new(ErrorCode::InvalidUtf8, format!("UTF-8 decode error: {}", e)))
+}
+
+fn header(unlock_after: i64) -> [u8; HEADER_LENGTH] {
+    let mut header = [0u8; HEADER_LENGTH];
+    header[..TIMELOCK_MAGIC.len()].copy_from_slice(TIMELOCK_MAGIC);
+    header[TIMELOCK_MAGIC.len()..].copy_from_slice(&unlock_after.to_be_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNLOCK: i64 = 1_800_000_000;
+
+    fn locked() -> String {
+        let encrypted = encrypt_data_timelocked("capsule".to_string(), "hunter2".to_string(), UNLOCK);
+        assert!(encrypted.success);
+        encrypted.data
+    }
+
+    #[test]
+    fn refuses_before_the_unlock_time() {
+        let result = decrypt_data_timelocked(locked(), "hunter2".to_string(), UNLOCK - 1);
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some("TIME_LOCKED"));
+    }
+
+    #[test]
+    fn decrypts_from_the_unlock_time_on() {
+        for trusted_time in [UNLOCK, UNLOCK + 86_400] {
+            let result = decrypt_data_timelocked(locked(), "hunter2".to_string(), trusted_time);
+            assert!(result.success, "{:?}", result.error);
+            assert_eq!(result.data, "capsule");
+        }
+    }
+
+    #[test]
+    fn moving_the_unlock_time_earlier_breaks_decryption() {
+        let mut blob = base64::decode(locked()).unwrap();
+        blob[TIMELOCK_MAGIC.len()..HEADER_LENGTH].copy_from_slice(&(UNLOCK - 86_400).to_be_bytes());
+
+        let result = decrypt_data_timelocked(base64::encode(blob), "hunter2".to_string(), UNLOCK - 1);
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some("AUTHENTICATION_FAILED"));
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        let plain = crate::encrypt_data("capsule".to_string(), "hunter2".to_string());
+        let result = decrypt_data_timelocked(plain.data, "hunter2".to_string(), UNLOCK);
+        assert_eq!(result.error_code.as_deref(), Some("INVALID_FORMAT"));
+    }
+}
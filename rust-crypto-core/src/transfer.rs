@@ -0,0 +1,422 @@
+// Device-to-device vault transfer over an untrusted channel (QR codes, short codes)
+//
+// Flow:
+//   1. The receiving device calls `create_transfer_offer()` and shows `public_key`.
+//   2. The sending device calls `accept_transfer_offer(public_key, vault, password)`
+//      and sends back the `response` (its own public key + sealed vault).
+//   3. The receiving device calls `offer.complete_transfer(response)`.
+//   4. Both devices display their SAS code; the users compare them. A mismatch
+//      means someone in the middle substituted keys and the result must be discarded.
+//
+// Key agreement is ephemeral X25519, keys are derived with HKDF-SHA256 and the
+// vault is sealed with AES-256-GCM. The vault travels still encrypted under the
+// user's master password, so plaintext never leaves the sending device.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey, X25519};
+use ring::rand::{SecureRandom, SystemRandom};
+use zeroize::Zeroize;
+
+use crate::{decrypt_data, hkdf_sha256, IV_LENGTH};
+
+// Domain separation for the HKDF expansion
+const TRANSFER_INFO: &[u8] = b"truefa-transfer-v1";
+
+// Derived material: 32 bytes of AEAD key followed by 32 bytes of SAS secret
+const TRANSFER_KEY_LENGTH: usize = 32;
+const SAS_SECRET_LENGTH: usize = 32;
+// Bytes of `compute_sas` HKDF output turned into the displayed code
+const SAS_SEED_LENGTH: usize = 4;
+const SAS_DIGITS: usize = 6;
+
+// Domain separation for `compute_sas`, distinct from the transfer key expansion
+const SAS_INFO: &[u8] = b"truefa-sas-v1";
+// Shortest key material accepted by `compute_sas` (128 bits)
+const MIN_SAS_SECRET_LENGTH: usize = 16;
+
+// Receiving side of a transfer, holding the ephemeral private key
+// The key is consumed by `complete_transfer`, so an offer can only be used once
+#[napi]
+pub struct TransferOffer {
+    private_key: Option<EphemeralPrivateKey>,
+    public_key: Vec<u8>,
+}
+
+#[napi]
+impl TransferOffer {
+    // Base64 encoded X25519 public key to hand to the sending device
+    #[napi(getter)]
+    pub fn public_key(&self) -> String {
+        base64::encode(&self.public_key)
+    }
+
+    // Open the sender's response; returns the still password-encrypted vault
+    #[napi]
+    pub fn complete_transfer(&mut self, response: TransferResponse) -> napi::Result<TransferCompletion> {
+        self.open_response(response).map(|(completion, _)| completion)
+    }
+}
+
+impl TransferOffer {
+    fn open_response(&mut self, response: TransferResponse) -> napi::Result<(TransferCompletion, TransferKeys)> {
+        let private_key = self
+            .private_key
+            .take()
+            .ok_or_else(|| napi::Error::from_reason("Transfer offer has already been used"))?;
+
+        let sender_public = decode_public_key(&response.public_key)?;
+        let sealed = base64::decode(&response.payload)
+            .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+        if sealed.len() < IV_LENGTH {
+            return Err(napi::Error::from_reason("Invalid transfer payload"));
+        }
+
+        let keys = agree(private_key, &sender_public, &self.public_key, &sender_public)?;
+        let sas = keys.sas()?;
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&keys.key));
+        let aad = [self.public_key.as_slice(), sender_public.as_slice()].concat();
+        let result = cipher.decrypt(
+            Nonce::from_slice(&sealed[..IV_LENGTH]),
+            Payload {
+                msg: &sealed[IV_LENGTH..],
+                aad: &aad,
+            },
+        );
+
+        let vault = result.map_err(|_| {
+            napi::Error::from_reason("Transfer payload failed authentication (tampered or wrong offer)")
+        })?;
+        let vault = String::from_utf8(vault)
+            .map_err(|e| napi::Error::from_reason(format!("UTF-8 decode error: {}", e)))?;
+
+        Ok((TransferCompletion { vault, sas }, keys))
+    }
+}
+
+// Message sent from the sending device back to the receiving device
+#[napi(object)]
+pub struct TransferResponse {
+    // Base64 encoded X25519 public key of the sender
+    pub public_key: String,
+    // Base64 encoded nonce + sealed vault
+    pub payload: String,
+}
+
+// Result of accepting an offer on the sending device
+#[napi(object)]
+pub struct TransferAcceptance {
+    // Send this to the receiving device
+    pub response: TransferResponse,
+    // Display locally only; never send it over the channel
+    pub sas: String,
+}
+
+// Result of completing a transfer on the receiving device
+#[napi(object)]
+pub struct TransferCompletion {
+    // The vault as it was stored on the sending device (master password encrypted)
+    pub vault: String,
+    // Short authentication string to compare with the sending device
+    pub sas: String,
+}
+
+// Start a transfer on the receiving device by generating an ephemeral keypair
+#[napi]
+pub fn create_transfer_offer() -> napi::Result<TransferOffer> {
+    let rng = SystemRandom::new();
+    let private_key = EphemeralPrivateKey::generate(&X25519, &rng)
+        .map_err(|_| napi::Error::from_reason("Failed to generate transfer key"))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| napi::Error::from_reason("Failed to compute transfer public key"))?
+        .as_ref()
+        .to_vec();
+
+    Ok(TransferOffer {
+        private_key: Some(private_key),
+        public_key,
+    })
+}
+
+// Seal an encrypted vault for the device that produced `offer_public_key`
+// The password is only used to confirm the user can unlock the vault being sent
+#[napi]
+pub fn accept_transfer_offer(
+    offer_public_key: String,
+    vault: String,
+    password: String,
+) -> napi::Result<TransferAcceptance> {
+    let receiver_public = decode_public_key(&offer_public_key)?;
+
+    // Refuse to send a vault the user cannot open
+    let mut check = decrypt_data(vault.clone(), password);
+    check.data.zeroize();
+    if !check.success {
+        return Err(napi::Error::from_reason(
+            check.error.unwrap_or_else(|| "Failed to unlock vault".to_string()),
+        ));
+    }
+
+    seal_for_receiver(&receiver_public, &vault).map(|(acceptance, _)| acceptance)
+}
+
+fn seal_for_receiver(receiver_public: &[u8], vault: &str) -> napi::Result<(TransferAcceptance, TransferKeys)> {
+    let rng = SystemRandom::new();
+    let private_key = EphemeralPrivateKey::generate(&X25519, &rng)
+        .map_err(|_| napi::Error::from_reason("Failed to generate transfer key"))?;
+    let sender_public = private_key
+        .compute_public_key()
+        .map_err(|_| napi::Error::from_reason("Failed to compute transfer public key"))?
+        .as_ref()
+        .to_vec();
+
+    let keys = agree(private_key, receiver_public, receiver_public, &sender_public)?;
+    let sas = keys.sas()?;
+
+    let mut iv = [0u8; IV_LENGTH];
+    rng.fill(&mut iv)
+        .map_err(|_| napi::Error::from_reason("Failed to generate nonce"))?;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&keys.key));
+    let aad = [receiver_public, sender_public.as_slice()].concat();
+    let result = cipher.encrypt(
+        Nonce::from_slice(&iv),
+        Payload {
+            msg: vault.as_bytes(),
+            aad: &aad,
+        },
+    );
+
+    let ciphertext = result.map_err(|e| napi::Error::from_reason(format!("Encryption error: {}", e)))?;
+    let mut sealed = Vec::with_capacity(iv.len() + ciphertext.len());
+    sealed.extend_from_slice(&iv);
+    sealed.extend_from_slice(&ciphertext);
+
+    let acceptance = TransferAcceptance {
+        response: TransferResponse {
+            public_key: base64::encode(&sender_public),
+            payload: base64::encode(&sealed),
+        },
+        sas,
+    };
+    Ok((acceptance, keys))
+}
+
+// Short authentication string for key material both sides derived independently
+// Equal inputs always give the same 6-digit code; users read it aloud and
+// compare, and a mismatch reveals a man-in-the-middle. The transfer protocol
+// shows this code too, computed over its HKDF-derived SAS secret.
+#[napi]
+pub fn compute_sas(shared_secret: Buffer) -> napi::Result<String> {
+    sas_for_secret(&shared_secret)
+}
+
+fn sas_for_secret(shared_secret: &[u8]) -> napi::Result<String> {
+    if shared_secret.len() < MIN_SAS_SECRET_LENGTH {
+        return Err(napi::Error::from_reason(format!(
+            "Shared secret must be at least {} bytes",
+            MIN_SAS_SECRET_LENGTH
+        )));
+    }
+
+    let mut seed = [0u8; SAS_SEED_LENGTH];
+    hkdf_sha256(shared_secret, &[], SAS_INFO, &mut seed)?;
+    let sas = sas_from_seed(&seed);
+    seed.zeroize();
+    Ok(sas)
+}
+
+fn decode_public_key(encoded: &str) -> napi::Result<Vec<u8>> {
+    let bytes = base64::decode(encoded)
+        .map_err(|e| napi::Error::from_reason(format!("Base64 decode error: {}", e)))?;
+    if bytes.len() != 32 {
+        return Err(napi::Error::from_reason("Invalid transfer public key"));
+    }
+    Ok(bytes)
+}
+
+// Keys both sides of a transfer derive from the X25519 exchange
+struct TransferKeys {
+    key: [u8; TRANSFER_KEY_LENGTH],
+    sas_secret: [u8; SAS_SECRET_LENGTH],
+}
+
+impl TransferKeys {
+    // The code both users compare, as `compute_sas` gives it for `sas_secret`
+    fn sas(&self) -> napi::Result<String> {
+        sas_for_secret(&self.sas_secret)
+    }
+}
+
+impl Drop for TransferKeys {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.sas_secret.zeroize();
+    }
+}
+
+// Run X25519 and expand the shared secret into key + SAS material
+// Both public keys are bound into the HKDF salt, always in receiver-then-sender order
+fn agree(
+    private_key: EphemeralPrivateKey,
+    peer_public: &[u8],
+    receiver_public: &[u8],
+    sender_public: &[u8],
+) -> napi::Result<TransferKeys> {
+    let salt = [receiver_public, sender_public].concat();
+
+    agreement::agree_ephemeral(
+        private_key,
+        &UnparsedPublicKey::new(&agreement::X25519, peer_public),
+        napi::Error::from_reason("Key agreement failed"),
+        |shared_secret| {
+            let mut okm = [0u8; TRANSFER_KEY_LENGTH + SAS_SECRET_LENGTH];
+            hkdf_sha256(shared_secret, &salt, TRANSFER_INFO, &mut okm)?;
+
+            let mut keys = TransferKeys {
+                key: [0u8; TRANSFER_KEY_LENGTH],
+                sas_secret: [0u8; SAS_SECRET_LENGTH],
+            };
+            keys.key.copy_from_slice(&okm[..TRANSFER_KEY_LENGTH]);
+            keys.sas_secret.copy_from_slice(&okm[TRANSFER_KEY_LENGTH..]);
+            okm.zeroize();
+            Ok(keys)
+        },
+    )
+}
+
+// Turn the SAS seed into a short decimal code that is easy to read aloud
+fn sas_from_seed(seed: &[u8]) -> String {
+    let value = u32::from_be_bytes([seed[0], seed[1], seed[2], seed[3]]);
+    format!("{:0>width$}", value % 10u32.pow(SAS_DIGITS as u32), width = SAS_DIGITS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> String {
+        crate::encrypt_data("{\"accounts\":[]}".to_string(), "hunter2".to_string()).data
+    }
+
+    // Flip one bit of a base64 field
+    fn tampered(encoded: &str, index: usize) -> String {
+        let mut bytes = base64::decode(encoded).unwrap();
+        bytes[index] ^= 0x01;
+        base64::encode(bytes)
+    }
+
+    #[test]
+    fn transfer_round_trip() {
+        let vault = vault();
+        let mut offer = create_transfer_offer().unwrap();
+        let acceptance = accept_transfer_offer(offer.public_key(), vault.clone(), "hunter2".to_string()).unwrap();
+        let completion = offer.complete_transfer(acceptance.response).unwrap();
+
+        assert_eq!(completion.vault, vault);
+        assert_eq!(completion.sas, acceptance.sas);
+        assert_eq!(completion.sas.len(), SAS_DIGITS);
+    }
+
+    #[test]
+    fn both_sides_show_compute_sas_of_the_shared_secret() {
+        let mut offer = create_transfer_offer().unwrap();
+        let receiver_public = offer.public_key.clone();
+        let (acceptance, sender_keys) = seal_for_receiver(&receiver_public, &vault()).unwrap();
+        let (completion, receiver_keys) = offer.open_response(acceptance.response).unwrap();
+
+        assert_eq!(sender_keys.sas_secret, receiver_keys.sas_secret);
+        assert_eq!(acceptance.sas, sas_for_secret(&sender_keys.sas_secret).unwrap());
+        assert_eq!(completion.sas, sas_for_secret(&receiver_keys.sas_secret).unwrap());
+    }
+
+    #[test]
+    fn offers_are_single_use() {
+        let vault = vault();
+        let mut offer = create_transfer_offer().unwrap();
+        let first = accept_transfer_offer(offer.public_key(), vault.clone(), "hunter2".to_string()).unwrap();
+        let second = accept_transfer_offer(offer.public_key(), vault, "hunter2".to_string()).unwrap();
+
+        assert!(offer.complete_transfer(first.response).is_ok());
+        assert!(offer.complete_transfer(second.response).is_err());
+    }
+
+    #[test]
+    fn tampered_responses_fail() {
+        let vault = vault();
+        let mut offer = create_transfer_offer().unwrap();
+        let acceptance = accept_transfer_offer(offer.public_key(), vault.clone(), "hunter2".to_string()).unwrap();
+        let response = TransferResponse {
+            public_key: acceptance.response.public_key,
+            payload: tampered(&acceptance.response.payload, IV_LENGTH + 1),
+        };
+        assert!(offer.complete_transfer(response).is_err());
+
+        // A substituted sender key changes both the agreed key and the AAD
+        let mut offer = create_transfer_offer().unwrap();
+        let acceptance = accept_transfer_offer(offer.public_key(), vault, "hunter2".to_string()).unwrap();
+        let response = TransferResponse {
+            public_key: tampered(&acceptance.response.public_key, 0),
+            payload: acceptance.response.payload,
+        };
+        assert!(offer.complete_transfer(response).is_err());
+    }
+
+    #[test]
+    fn responses_only_open_for_their_offer() {
+        let vault = vault();
+        let intended = create_transfer_offer().unwrap();
+        let mut other = create_transfer_offer().unwrap();
+
+        let acceptance = accept_transfer_offer(intended.public_key(), vault.clone(), "hunter2".to_string()).unwrap();
+        assert!(other.complete_transfer(acceptance.response).is_err());
+
+        // A tampered offer key was agreed with nobody's private key
+        let mut offer = create_transfer_offer().unwrap();
+        let acceptance =
+            accept_transfer_offer(tampered(&offer.public_key(), 0), vault, "hunter2".to_string()).unwrap();
+        assert!(offer.complete_transfer(acceptance.response).is_err());
+    }
+
+    #[test]
+    fn accept_rejects_bad_input() {
+        let offer = create_transfer_offer().unwrap();
+        assert!(accept_transfer_offer(offer.public_key(), vault(), "hunter3".to_string()).is_err());
+        assert!(accept_transfer_offer(base64::encode([0u8; 16]), vault(), "hunter2".to_string()).is_err());
+    }
+
+    #[test]
+    fn matching_secrets_give_matching_sas() {
+        let secret = [0x42u8; 32];
+        let sas = sas_for_secret(&secret).unwrap();
+        assert_eq!(sas, sas_for_secret(&[0x42u8; 32]).unwrap());
+        assert_eq!(sas.len(), SAS_DIGITS);
+        assert!(sas.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn differing_secrets_give_differing_sas() {
+        let mut secret = [0x42u8; 32];
+        let original = sas_for_secret(&secret).unwrap();
+        secret[31] ^= 0x01;
+        assert_ne!(sas_for_secret(&secret).unwrap(), original);
+    }
+
+    #[test]
+    fn sas_rejects_short_secrets() {
+        assert!(sas_for_secret(&[0u8; MIN_SAS_SECRET_LENGTH - 1]).is_err());
+        assert!(sas_for_secret(&[0u8; MIN_SAS_SECRET_LENGTH]).is_ok());
+    }
+
+    #[test]
+    fn sas_keeps_leading_zeros() {
+        assert_eq!(sas_from_seed(&[0, 0, 0, 42]), "000042");
+        assert_eq!(sas_from_seed(&[0xFF; 4]), "967295");
+    }
+}